@@ -0,0 +1,131 @@
+// Runs roms with and without the decode cache, and shows how much faster the cached
+// interpreter is and that both end up in exactly the same state:
+//
+//     cargo run --release --example decode_cache -- [--frames N] [rom...]
+//
+// Without roms it runs a built-in loop that rewrites one of its own instructions with
+// Fx55 on every pass, so a cache that missed the write would come out different.
+// Exits with 1 if any rom ended differently. Timings are noisy on a busy machine; run
+// it a few times, with --release.
+
+use chip8_rs::chip8::{Chip8, Chip8Error};
+use chip8_rs::rom::Rom;
+use std::process;
+use std::time::{Duration, Instant};
+
+// Instructions per frame, high enough that timers and frame overhead don't count
+const TICK_RATE: usize = 10_000;
+
+const SELF_MODIFYING: [u8; 24] = [
+    0x6E, 0x00, // 200: LD VE, 0
+    0x60, 0x6A, // 202: LD V0, 0x6A
+    0x7E, 0x01, // 204: ADD VE, 1
+    0x81, 0xE0, // 206: LD V1, VE
+    0xA2, 0x12, // 208: LD I, 0x212
+    0xF1, 0x55, // 20A: LD [I], V1      rewrites 212 as LD VA, VE
+    0x8B, 0xA4, // 20C: ADD VB, VA
+    0xA3, 0x00, // 20E: LD I, 0x300
+    0xFE, 0x33, // 210: LD B, VE
+    0x00, 0x00, // 212: rewritten above
+    0xD0, 0x13, // 214: DRW V0, V1, 3
+    0x12, 0x04, // 216: JP 0x204
+];
+
+struct Run {
+    time: Duration,
+    instructions: u64,
+    state: Vec<u8>,
+    error: Option<Chip8Error>,
+}
+
+fn run(rom: &Rom, frames: u64, decode_cache: bool) -> Run {
+    let mut chip8 = Chip8::new();
+    chip8.load(rom).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(2);
+    });
+    chip8.seed(1);
+    chip8.tick_rate = TICK_RATE;
+    chip8.decode_cache = decode_cache;
+
+    let start = Instant::now();
+    let mut error = None;
+    let mut done = 0;
+    while done < frames {
+        if let Err(e) = chip8.frame() {
+            error = Some(e);
+            break;
+        }
+        done += 1;
+    }
+    Run {
+        time: start.elapsed(),
+        instructions: done * TICK_RATE as u64,
+        state: chip8.save_state(),
+        error,
+    }
+}
+
+fn mips(run: &Run) -> f64 {
+    run.instructions as f64 / run.time.as_secs_f64() / 1e6
+}
+
+fn main() {
+    let mut frames = 1000;
+    let mut paths = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--frames" {
+            frames = args.next().and_then(|n| n.parse().ok()).unwrap_or_else(|| {
+                eprintln!("--frames needs a number");
+                process::exit(2);
+            });
+        } else {
+            paths.push(arg);
+        }
+    }
+
+    let roms: Vec<(String, Rom)> = if paths.is_empty() {
+        vec![(
+            "self-modifying loop".into(),
+            Rom::from_bytes(SELF_MODIFYING).unwrap(),
+        )]
+    } else {
+        paths
+            .into_iter()
+            .map(|path| {
+                let rom = Rom::from_path(&path).unwrap_or_else(|e| {
+                    eprintln!("{}: {}", path, e);
+                    process::exit(2);
+                });
+                (path, rom)
+            })
+            .collect()
+    };
+
+    let mut all_same = true;
+    for (name, rom) in roms.iter() {
+        let uncached = run(rom, frames, false);
+        let cached = run(rom, frames, true);
+        let same = uncached.state == cached.state && uncached.error == cached.error;
+        all_same &= same;
+        println!(
+            "{}: {:.1} M instructions/s uncached, {:.1} cached ({:.2}x), {}",
+            name,
+            mips(&uncached),
+            mips(&cached),
+            uncached.time.as_secs_f64() / cached.time.as_secs_f64(),
+            if same {
+                "same state"
+            } else {
+                "DIFFERENT STATE"
+            }
+        );
+        if let Some(e) = cached.error {
+            println!("    stopped early: {}", e);
+        }
+    }
+    if !all_same {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,186 @@
+// Runs roms in the interpreter and with the JIT, and shows how much faster the JIT is
+// and that both end up in exactly the same state:
+//
+//     cargo run --release --features jit --example jit -- [--frames N] [rom...]
+//
+// Every rom runs with each quirks preset, once at a high tick rate and once at a tick
+// rate that keeps cutting blocks short at the end of a frame, for as many instructions
+// as N frames at the high one (1000 by default). Without roms it runs two
+// built-in loops: one going through every compiled instruction with VF as an operand,
+// and one rewriting its own code with Fx55. Exits with 1 if any run ended differently.
+
+use chip8_rs::chip8::{Chip8, Chip8Error};
+use chip8_rs::jit::Jit;
+use chip8_rs::quirks::Quirks;
+use chip8_rs::rom::Rom;
+use std::process;
+use std::time::{Duration, Instant};
+
+// Instructions per frame: high enough that timers and frame overhead don't count, and
+// low enough that most blocks don't fit what's left of the frame
+const TICK_RATES: [usize; 2] = [10_000, 7];
+
+const ARITHMETIC: [u8; 58] = [
+    0x6E, 0x00, // 200: LD VE, 0
+    0x7E, 0x01, // 202: ADD VE, 1
+    0x80, 0xE0, // 204: LD V0, VE
+    0x81, 0xE4, // 206: ADD V1, VE
+    0x8F, 0x14, // 208: ADD VF, V1
+    0x82, 0xF5, // 20A: SUB V2, VF
+    0x8F, 0x27, // 20C: SUBN VF, V2
+    0x83, 0x16, // 20E: SHR V3, V1
+    0x8F, 0x3E, // 210: SHL VF, V3
+    0x84, 0x31, // 212: OR V4, V3
+    0x85, 0x42, // 214: AND V5, V4
+    0x86, 0x53, // 216: XOR V6, V5
+    0xC7, 0xFF, // 218: RND V7, 0xFF    interpreted, ends a block
+    0x87, 0x74, // 21A: ADD V7, V7
+    0xF7, 0x1E, // 21C: ADD I, V7
+    0xF0, 0x29, // 21E: LD F, V0
+    0xF1, 0x15, // 220: LD DT, V1
+    0xF8, 0x07, // 222: LD V8, DT
+    0xF2, 0x18, // 224: LD ST, V2
+    0x50, 0x10, // 226: SE V0, V1
+    0x8F, 0x06, // 228: SHR VF, V0
+    0x91, 0x20, // 22A: SNE V1, V2
+    0x8F, 0xF4, // 22C: ADD VF, VF
+    0x3E, 0x00, // 22E: SE VE, 0
+    0x12, 0x02, // 230: JP 0x202
+    0xA3, 0x00, // 232: LD I, 0x300      every 256 passes
+    0xF7, 0x33, // 234: LD B, V7
+    0xD0, 0x15, // 236: DRW V0, V1, 5
+    0x12, 0x02, // 238: JP 0x202
+];
+
+const SELF_MODIFYING: [u8; 24] = [
+    0x6E, 0x00, // 200: LD VE, 0
+    0x60, 0x6A, // 202: LD V0, 0x6A
+    0x7E, 0x01, // 204: ADD VE, 1
+    0x81, 0xE0, // 206: LD V1, VE
+    0xA2, 0x12, // 208: LD I, 0x212
+    0xF1, 0x55, // 20A: LD [I], V1      rewrites 212 as LD VA, VE
+    0x8B, 0xA4, // 20C: ADD VB, VA
+    0xA3, 0x00, // 20E: LD I, 0x300
+    0xFE, 0x33, // 210: LD B, VE
+    0x00, 0x00, // 212: rewritten above
+    0xD0, 0x13, // 214: DRW V0, V1, 3
+    0x12, 0x04, // 216: JP 0x204
+];
+
+struct Run {
+    time: Duration,
+    state: Vec<u8>,
+    error: Option<Chip8Error>,
+}
+
+fn run(rom: &Rom, quirks: Quirks, tick_rate: usize, frames: u64, mut jit: Option<&mut Jit>) -> Run {
+    let mut chip8 = Chip8::new();
+    chip8.load(rom).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(2);
+    });
+    chip8.seed(1);
+    chip8.quirks = quirks;
+    chip8.tick_rate = tick_rate;
+
+    let start = Instant::now();
+    let mut error = None;
+    for _ in 0..frames {
+        let result = match jit.as_deref_mut() {
+            Some(jit) => jit.frame(&mut chip8),
+            None => chip8.frame(),
+        };
+        if let Err(e) = result {
+            error = Some(e);
+            break;
+        }
+    }
+    Run {
+        time: start.elapsed(),
+        state: chip8.save_state(),
+        error,
+    }
+}
+
+fn main() {
+    let mut frames = 1000;
+    let mut paths = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--frames" {
+            frames = args.next().and_then(|n| n.parse().ok()).unwrap_or_else(|| {
+                eprintln!("--frames needs a number");
+                process::exit(2);
+            });
+        } else {
+            paths.push(arg);
+        }
+    }
+
+    let roms: Vec<(String, Rom)> = if paths.is_empty() {
+        vec![
+            (
+                "arithmetic loop".into(),
+                Rom::from_bytes(ARITHMETIC).unwrap(),
+            ),
+            (
+                "self-modifying loop".into(),
+                Rom::from_bytes(SELF_MODIFYING).unwrap(),
+            ),
+        ]
+    } else {
+        paths
+            .into_iter()
+            .map(|path| {
+                let rom = Rom::from_path(&path).unwrap_or_else(|e| {
+                    eprintln!("{}: {}", path, e);
+                    process::exit(2);
+                });
+                (path, rom)
+            })
+            .collect()
+    };
+    let presets = [
+        ("default", Quirks::default()),
+        ("chip8", Quirks::chip8()),
+        ("superchip", Quirks::superchip()),
+        ("xochip", Quirks::xochip()),
+    ];
+
+    let mut jit = Jit::new().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(2);
+    });
+    let mut all_same = true;
+    for (name, rom) in roms.iter() {
+        for (preset, quirks) in presets {
+            for tick_rate in TICK_RATES {
+                let frames = frames * TICK_RATES[0] as u64 / tick_rate as u64;
+                let interpreted = run(rom, quirks, tick_rate, frames, None);
+                let compiled = run(rom, quirks, tick_rate, frames, Some(&mut jit));
+                let same =
+                    interpreted.state == compiled.state && interpreted.error == compiled.error;
+                all_same &= same;
+                println!(
+                    "{}, {} quirks, {} per frame: {:.2}x, {}",
+                    name,
+                    preset,
+                    tick_rate,
+                    interpreted.time.as_secs_f64() / compiled.time.as_secs_f64(),
+                    if same {
+                        "same state"
+                    } else {
+                        "DIFFERENT STATE"
+                    }
+                );
+                if let Some(e) = compiled.error {
+                    println!("    stopped early: {}", e);
+                }
+            }
+        }
+    }
+    println!("{} blocks compiled", jit.compiled);
+    if !all_same {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,63 @@
+// Builds a rom database from chip-8-database's programs.json, keeping only the programs
+// of the given roms, e.g. to regenerate the bundled one from the roms the web page
+// offers:
+//
+//     cargo run --example romdb_import -- programs.json roms/*.ch8 > src/romdb.json
+//
+// programs.json is database/programs.json from https://github.com/chip-8/chip-8-database.
+// Roms it doesn't know are listed on stderr, and make it exit with 1 after writing the
+// rest.
+
+use chip8_rs::rom::Rom;
+use chip8_rs::romdb::select_programs;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::process;
+
+fn main() {
+    let args: Vec<PathBuf> = std::env::args_os().skip(1).map(PathBuf::from).collect();
+    let Some((programs_path, rom_paths)) = args.split_first().filter(|(_, roms)| !roms.is_empty())
+    else {
+        eprintln!("usage: romdb_import <programs.json> <rom>...");
+        process::exit(2);
+    };
+    let json = std::fs::read_to_string(programs_path).unwrap_or_else(|e| {
+        eprintln!("{}: {}", programs_path.display(), e);
+        process::exit(2);
+    });
+    let roms: Vec<Rom> = rom_paths
+        .iter()
+        .map(|path| {
+            Rom::from_path(path).unwrap_or_else(|e| {
+                eprintln!("{}: {}", path.display(), e);
+                process::exit(2);
+            })
+        })
+        .collect();
+    let programs = select_programs(&json, &roms).unwrap_or_else(|e| {
+        eprintln!("{}: {}", programs_path.display(), e);
+        process::exit(2);
+    });
+    println!("{}", serde_json::to_string_pretty(&programs).unwrap());
+
+    let found: HashSet<String> = programs
+        .iter()
+        .filter_map(|program| program["roms"].as_object())
+        .flat_map(|roms| roms.keys().map(|sha1| sha1.to_ascii_lowercase()))
+        .collect();
+    let mut missing = false;
+    for (path, rom) in rom_paths.iter().zip(roms.iter()) {
+        if !found.contains(&rom.sha1_hex()) {
+            eprintln!(
+                "{}: not in {} (sha1 {})",
+                path.display(),
+                programs_path.display(),
+                rom.sha1_hex()
+            );
+            missing = true;
+        }
+    }
+    if missing {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,535 @@
+use crate::chip8::CHIP8_RAM;
+use crate::profile::Profile;
+use crate::rom::Rom;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+// Static checks of a rom, without running it. Code is found by following the control
+// flow from the start address, so data is never mistaken for instructions, but code
+// only reached through Bnnn or self-modification isn't seen either.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    // `Chip8::exec` fails on it, if it's reached
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Problem {
+    UnknownOpcode,
+    // 0nnn runs machine code on the original hardware and is skipped here
+    MachineCode,
+    // 00EE where nothing was called
+    ReturnOutsideSubroutine,
+    // A subroutine without a reachable 00EE
+    NoReturn,
+    Recursion { target: usize },
+    // The calls nest deeper than the stack holds, without end (`None`) when they recurse
+    // with no way out
+    StackOverflow { depth: Option<usize>, limit: usize },
+    JumpOutsideRom { target: usize },
+    // Execution carries on past the last instruction of the rom
+    RunsOffRom,
+    // Bnnn; where it goes isn't followed
+    ComputedJump,
+    // I + len runs past the end of ram, with I known from an earlier Annn
+    MemoryOutOfRange { i: usize, len: usize },
+    // What the instruction does depends on these quirks, see `Quirks`
+    Quirks { quirks: &'static str },
+}
+
+impl Problem {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Problem::UnknownOpcode
+            | Problem::ReturnOutsideSubroutine
+            | Problem::StackOverflow { .. }
+            | Problem::MemoryOutOfRange { .. } => Severity::Error,
+            Problem::MachineCode
+            | Problem::NoReturn
+            | Problem::Recursion { .. }
+            | Problem::JumpOutsideRom { .. }
+            | Problem::RunsOffRom => Severity::Warning,
+            Problem::ComputedJump | Problem::Quirks { .. } => Severity::Info,
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::UnknownOpcode => write!(f, "unknown opcode"),
+            Problem::MachineCode => write!(f, "machine code routine, ignored"),
+            Problem::ReturnOutsideSubroutine => write!(f, "return outside of a subroutine"),
+            Problem::NoReturn => write!(f, "subroutine never returns"),
+            Problem::Recursion { target } => write!(f, "recursive call to {:#05x}", target),
+            Problem::StackOverflow {
+                depth: Some(depth),
+                limit,
+            } => write!(f, "calls nest {} deep, the stack holds {}", depth, limit),
+            Problem::StackOverflow { depth: None, limit } => {
+                write!(f, "recurses with no way out, the stack holds {}", limit)
+            }
+            Problem::JumpOutsideRom { target } => {
+                write!(f, "jumps outside the rom to {:#05x}", target)
+            }
+            Problem::RunsOffRom => write!(f, "runs past the end of the rom"),
+            Problem::ComputedJump => write!(f, "computed jump, not followed"),
+            Problem::MemoryOutOfRange { i, len } => {
+                write!(
+                    f,
+                    "accesses {} bytes from I={:#05x}, past the end of ram",
+                    len, i
+                )
+            }
+            Problem::Quirks { quirks } => write!(f, "depends on quirks: {}", quirks),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Diagnostic {
+    pub addr: usize,
+    pub opcode: u16,
+    pub severity: Severity,
+    #[serde(flatten)]
+    pub problem: Problem,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(
+            f,
+            "{:#05x}  {:04x}  {}: {}",
+            self.addr, self.opcode, severity, self.problem
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Analysis {
+    // By address
+    pub diagnostics: Vec<Diagnostic>,
+    // Addresses of the instructions reached
+    pub reachable: BTreeSet<usize>,
+    // Entry points of the called subroutines
+    pub subroutines: BTreeSet<usize>,
+    // `None` when calls can recurse, so there's no telling how deep they go
+    pub max_call_depth: Option<usize>,
+}
+
+impl Analysis {
+    pub fn of(rom: &Rom) -> Self {
+        Self::with_profile(rom, &Profile::default())
+    }
+
+    // For roms loaded and started where `profile` says
+    pub fn with_profile(rom: &Rom, profile: &Profile) -> Self {
+        let mut walker = Walker::new(rom, profile.load_addr);
+        walker.walk_all(profile.start_pc);
+        walker.finish(profile.stack.depth)
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|d| d.severity == Severity::Error)
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|d| d.severity == severity)
+            .count()
+    }
+}
+
+impl fmt::Display for Analysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let depth = match self.max_call_depth {
+            Some(depth) => depth.to_string(),
+            None => "unbounded".to_string(),
+        };
+        writeln!(
+            f,
+            "{} instructions reached, {} subroutines, call depth {}",
+            self.reachable.len(),
+            self.subroutines.len(),
+            depth
+        )?;
+        for diagnostic in self.diagnostics.iter() {
+            writeln!(f, "{}", diagnostic)?;
+        }
+        write!(
+            f,
+            "{} errors, {} warnings",
+            self.count(Severity::Error),
+            self.count(Severity::Warning)
+        )
+    }
+}
+
+// Whether I holds a known address: after Annn, until something changes it by an
+// amount that isn't known.
+type KnownI = Option<usize>;
+
+#[derive(Default)]
+struct Subroutine {
+    // (call site, target)
+    calls: BTreeSet<(usize, usize)>,
+    returns: bool,
+    // Where execution can go from each instruction reached; none where it ends
+    next: BTreeMap<usize, Vec<usize>>,
+}
+
+impl Subroutine {
+    // Whether execution from the entry can return, or end some other way, without
+    // going through any of the calls at `sites`
+    fn escapes(&self, entry: usize, sites: &BTreeSet<usize>) -> bool {
+        let mut seen = BTreeSet::new();
+        let mut pending = vec![entry];
+        while let Some(addr) = pending.pop() {
+            if sites.contains(&addr) || !seen.insert(addr) {
+                continue;
+            }
+            match self.next.get(&addr) {
+                Some(next) if !next.is_empty() => pending.extend(next.iter().copied()),
+                _ => return true,
+            }
+        }
+        false
+    }
+}
+
+struct Walker {
+    ram: Vec<u8>,
+    start: usize,
+    end: usize,
+    entry: usize,
+    main: usize,
+    reachable: BTreeSet<usize>,
+    subroutines: BTreeMap<usize, Subroutine>,
+    diagnostics: BTreeSet<Diagnostic>,
+}
+
+impl Walker {
+    fn new(rom: &Rom, load_addr: usize) -> Self {
+        let mut ram = vec![0; CHIP8_RAM];
+        let end = (load_addr + rom.size()).min(CHIP8_RAM);
+        if load_addr < end {
+            ram[load_addr..end].copy_from_slice(&rom.bytes()[..end - load_addr]);
+        }
+        Self {
+            ram,
+            start: load_addr,
+            end,
+            entry: 0,
+            main: 0,
+            reachable: BTreeSet::new(),
+            subroutines: BTreeMap::new(),
+            diagnostics: BTreeSet::new(),
+        }
+    }
+
+    fn in_rom(&self, addr: usize) -> bool {
+        addr >= self.start && addr + 2 <= self.end
+    }
+
+    fn opcode(&self, addr: usize) -> u16 {
+        (self.ram[addr] as u16) << 8 | self.ram.get(addr + 1).copied().unwrap_or(0) as u16
+    }
+
+    fn report(&mut self, addr: usize, problem: Problem) {
+        let addr = addr.min(CHIP8_RAM - 1);
+        self.diagnostics.insert(Diagnostic {
+            addr,
+            opcode: self.opcode(addr),
+            severity: problem.severity(),
+            problem,
+        });
+    }
+
+    // The program from `start_pc` and every subroutine it calls
+    fn walk_all(&mut self, start_pc: usize) {
+        self.main = start_pc;
+        let mut pending = vec![start_pc];
+        while let Some(entry) = pending.pop() {
+            if self.subroutines.contains_key(&entry) {
+                continue;
+            }
+            if !self.in_rom(entry) {
+                self.report(entry, Problem::RunsOffRom);
+                continue;
+            }
+            let subroutine = self.walk(entry);
+            for (site, target) in subroutine.calls.iter() {
+                if self.in_rom(*target) {
+                    pending.push(*target);
+                } else {
+                    self.report(*site, Problem::JumpOutsideRom { target: *target });
+                }
+            }
+            if entry != self.main && !subroutine.returns {
+                self.report(entry, Problem::NoReturn);
+            }
+            self.subroutines.insert(entry, subroutine);
+        }
+    }
+
+    // Follows one subroutine (or the main program) without going into the calls it makes.
+    fn walk(&mut self, entry: usize) -> Subroutine {
+        self.entry = entry;
+        let mut subroutine = Subroutine::default();
+        // I is only known at the very start; subroutines can be called with anything
+        let i = if entry == self.main { Some(0) } else { None };
+        let mut seen: BTreeMap<usize, KnownI> = BTreeMap::new();
+        let mut pending = vec![(entry, i)];
+        while let Some((addr, i)) = pending.pop() {
+            // Seeing an address again only matters if I is less certain than before
+            let i = match seen.get(&addr) {
+                Some(None) => continue,
+                Some(known) if *known == i => continue,
+                Some(_) => None,
+                None => i,
+            };
+            seen.insert(addr, i);
+            self.reachable.insert(addr);
+            let mut next = Vec::new();
+            for (to, i) in self.step(addr, i, &mut subroutine) {
+                if self.in_rom(to) {
+                    next.push(to);
+                    pending.push((to, i));
+                }
+            }
+            subroutine.next.insert(addr, next);
+        }
+        subroutine
+    }
+
+    // Where execution can go after the instruction at `addr`, and what's known about I there.
+    fn step(
+        &mut self,
+        addr: usize,
+        i: KnownI,
+        subroutine: &mut Subroutine,
+    ) -> Vec<(usize, KnownI)> {
+        let opcode = self.opcode(addr);
+        let x = ((opcode & 0x0F00) >> 8) as usize;
+        let y = ((opcode & 0x00F0) >> 4) as usize;
+        let n = (opcode & 0x000F) as usize;
+        let nnn = (opcode & 0x0FFF) as usize;
+
+        match (opcode >> 12, x, y, n) {
+            (0x0, 0x0, 0xE, 0x0) => self.fall_through(addr, i),
+            (0x0, 0x0, 0xE, 0xE) => {
+                if self.entry == self.main {
+                    self.report(addr, Problem::ReturnOutsideSubroutine);
+                }
+                subroutine.returns = true;
+                vec![]
+            }
+            (0x0, _, _, _) => {
+                self.report(addr, Problem::MachineCode);
+                self.fall_through(addr, i)
+            }
+            (0x1, _, _, _) => {
+                // A jump to itself is how programs stop
+                if nnn == addr {
+                    vec![]
+                } else {
+                    self.jump(addr, nnn, i)
+                }
+            }
+            (0x2, _, _, _) => {
+                subroutine.calls.insert((addr, nnn));
+                // Whatever the subroutine did to I is unknown here
+                self.fall_through(addr, None)
+            }
+            (0x3, _, _, _) | (0x4, _, _, _) | (0x5, _, _, 0x0) | (0x9, _, _, 0x0) => {
+                self.skip(addr, i)
+            }
+            (0xE, _, 0x9, 0xE) | (0xE, _, 0xA, 0x1) => self.skip(addr, i),
+            (0x6, _, _, _) | (0x7, _, _, _) | (0xC, _, _, _) => self.fall_through(addr, i),
+            (0x8, _, _, 0x0 | 0x4 | 0x5 | 0x7) => self.fall_through(addr, i),
+            (0x8, _, _, 0x1..=0x3) => {
+                self.report(addr, Problem::Quirks { quirks: "vf_reset" });
+                self.fall_through(addr, i)
+            }
+            (0x8, _, _, 0x6 | 0xE) => {
+                // Shifting Vx or Vy is the same thing when they're the same register
+                if x != y {
+                    self.report(addr, Problem::Quirks { quirks: "shifting" });
+                }
+                self.fall_through(addr, i)
+            }
+            (0xA, _, _, _) => self.fall_through(addr, Some(nnn)),
+            (0xB, _, _, _) => {
+                self.report(addr, Problem::ComputedJump);
+                self.report(addr, Problem::Quirks { quirks: "jumping" });
+                vec![]
+            }
+            (0xD, _, _, _) => {
+                self.check_memory(addr, i, n);
+                self.report(
+                    addr,
+                    Problem::Quirks {
+                        quirks: "display_wait, clipping",
+                    },
+                );
+                self.fall_through(addr, i)
+            }
+            (0xF, _, 0x0, 0x7) | (0xF, _, 0x0, 0xA) | (0xF, _, 0x1, 0x5) | (0xF, _, 0x1, 0x8) => {
+                self.fall_through(addr, i)
+            }
+            // I moves by a register's value
+            (0xF, _, 0x1, 0xE) | (0xF, _, 0x2, 0x9) => self.fall_through(addr, None),
+            (0xF, _, 0x3, 0x3) => {
+                self.check_memory(addr, i, 3);
+                self.fall_through(addr, i)
+            }
+            (0xF, _, 0x5 | 0x6, 0x5) => {
+                self.check_memory(addr, i, x + 1);
+                self.report(addr, Problem::Quirks { quirks: "memory" });
+                // Moved on or not, depending on the memory quirk
+                self.fall_through(addr, None)
+            }
+            _ => {
+                self.report(addr, Problem::UnknownOpcode);
+                vec![]
+            }
+        }
+    }
+
+    fn fall_through(&mut self, addr: usize, i: KnownI) -> Vec<(usize, KnownI)> {
+        if !self.in_rom(addr + 2) {
+            self.report(addr, Problem::RunsOffRom);
+            return vec![];
+        }
+        vec![(addr + 2, i)]
+    }
+
+    fn skip(&mut self, addr: usize, i: KnownI) -> Vec<(usize, KnownI)> {
+        let mut next = self.fall_through(addr, i);
+        if self.in_rom(addr + 4) {
+            next.push((addr + 4, i));
+        } else if !next.is_empty() {
+            self.report(addr, Problem::RunsOffRom);
+        }
+        next
+    }
+
+    fn jump(&mut self, addr: usize, target: usize, i: KnownI) -> Vec<(usize, KnownI)> {
+        if !self.in_rom(target) {
+            self.report(addr, Problem::JumpOutsideRom { target });
+            return vec![];
+        }
+        vec![(target, i)]
+    }
+
+    fn check_memory(&mut self, addr: usize, i: KnownI, len: usize) {
+        if let Some(i) = i {
+            if len > 0 && i + len > CHIP8_RAM {
+                self.report(addr, Problem::MemoryOutOfRange { i, len });
+            }
+        }
+    }
+
+    // Deepest nesting of calls below `entry`, reporting recursion on the way. `None` when
+    // a call below it recurses.
+    fn depth(
+        &mut self,
+        entry: usize,
+        limit: usize,
+        path: &mut Vec<usize>,
+        memo: &mut BTreeMap<usize, Option<usize>>,
+    ) -> Option<usize> {
+        if let Some(depth) = memo.get(&entry) {
+            return *depth;
+        }
+        path.push(entry);
+        let calls: Vec<(usize, usize)> = self
+            .subroutines
+            .get(&entry)
+            .map(|s| s.calls.iter().copied().collect())
+            .unwrap_or_default();
+        let mut deepest = Some(0);
+        for (site, target) in calls {
+            if !self.subroutines.contains_key(&target) {
+                continue;
+            }
+            if let Some(start) = path.iter().position(|addr| *addr == target) {
+                let problem = if self.escapes(&path[start..]) {
+                    Problem::Recursion { target }
+                } else {
+                    Problem::StackOverflow { depth: None, limit }
+                };
+                self.report(site, problem);
+                deepest = None;
+                continue;
+            }
+            let depth = self.depth(target, limit, path, memo);
+            deepest = deepest.zip(depth).map(|(d, depth)| d.max(1 + depth));
+        }
+        path.pop();
+        memo.insert(entry, deepest);
+        deepest
+    }
+
+    // Whether a cycle of calls, each subroutine in `cycle` calling the next and the last
+    // calling the first, can stop: when one of them can get out without its call.
+    fn escapes(&self, cycle: &[usize]) -> bool {
+        cycle.iter().enumerate().any(|(n, entry)| {
+            let callee = cycle[(n + 1) % cycle.len()];
+            let subroutine = &self.subroutines[entry];
+            let sites = subroutine
+                .calls
+                .iter()
+                .filter(|(_, target)| *target == callee)
+                .map(|(site, _)| *site)
+                .collect();
+            subroutine.escapes(*entry, &sites)
+        })
+    }
+
+    fn finish(mut self, limit: usize) -> Analysis {
+        let main = self.main;
+        let mut memo = BTreeMap::new();
+        let depth = self.depth(main, limit, &mut Vec::new(), &mut memo);
+        if let Some(depth) = depth.filter(|depth| *depth > limit) {
+            // Report it at the call from the main program that leads down there
+            let site = self.subroutines[&main]
+                .calls
+                .iter()
+                .find(|(_, target)| memo.get(target).is_some_and(|d| *d == Some(depth - 1)))
+                .map_or(main, |(site, _)| *site);
+            self.report(
+                site,
+                Problem::StackOverflow {
+                    depth: Some(depth),
+                    limit,
+                },
+            );
+        }
+        Analysis {
+            diagnostics: self.diagnostics.into_iter().collect(),
+            reachable: self.reachable,
+            subroutines: self
+                .subroutines
+                .keys()
+                .copied()
+                .filter(|addr| *addr != main)
+                .collect(),
+            max_call_depth: depth,
+        }
+    }
+}
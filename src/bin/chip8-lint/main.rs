@@ -0,0 +1,47 @@
+use chip8_rs::analysis::{Analysis, Severity};
+use chip8_rs::config::ProfileConfig;
+use chip8_rs::rom::Rom;
+use clap::Parser;
+use std::path::PathBuf;
+use std::process;
+
+/// Checks a CHIP-8 ROM for problems without running it
+///
+/// Follows the code from the start address and reports unknown opcodes, unbalanced
+/// calls and returns, calls nesting deeper than the stack, jumps out of the ROM and
+/// memory accesses past the end of RAM. Exits with 1 if any of them is an error.
+#[derive(Parser)]
+#[command(name = "chip8-lint", version)]
+struct Args {
+    /// ROM to check
+    rom: PathBuf,
+    /// Also list instructions that depend on quirks, and computed jumps
+    #[arg(long)]
+    info: bool,
+    /// Print the diagnostics as JSON
+    #[arg(long)]
+    json: bool,
+    #[command(flatten)]
+    profile: ProfileConfig,
+}
+
+fn main() {
+    let args = Args::parse();
+    let rom = Rom::from_path(&args.rom).unwrap_or_else(|e| {
+        eprintln!("{}: {}", args.rom.display(), e);
+        process::exit(2);
+    });
+    let mut analysis = Analysis::with_profile(&rom, &args.profile.profile());
+    if !args.info {
+        analysis.diagnostics.retain(|d| d.severity > Severity::Info);
+    }
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&analysis).unwrap());
+    } else {
+        println!("{}", analysis);
+    }
+    if analysis.has_errors() {
+        process::exit(1);
+    }
+}
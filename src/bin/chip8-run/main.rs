@@ -0,0 +1,247 @@
+mod script;
+
+use chip8_rs::chip8::{Chip8, Chip8Error};
+use chip8_rs::config::MachineConfig;
+use chip8_rs::coverage::Coverage;
+use chip8_rs::heatmap::Heatmap;
+use chip8_rs::machine::Machine;
+use chip8_rs::palette::Palette;
+use chip8_rs::profiler::Profiler;
+use chip8_rs::rom::Rom;
+use chip8_rs::screenshot;
+use clap::Parser;
+use script::Script;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+// Exit codes besides 0 (halted, or ran out of frames)
+const EXIT_ERRORED: i32 = 1;
+// Also what clap exits with for bad arguments
+const EXIT_USAGE: i32 = 2;
+const EXIT_TIMED_OUT: i32 = 3;
+
+/// Runs a CHIP-8 ROM without a window and reports how it ended
+///
+/// Prints the final screen, the registers and the status: halted (stuck on a jump to
+/// itself), errored (e.g. a stack overflow) or timed out (still running after --frames).
+/// Exits with 1 on an emulator error, and with 3 on a time out if --expect-halt is given.
+#[derive(Parser)]
+#[command(name = "chip8-run", version)]
+struct Args {
+    /// ROM to run
+    rom: PathBuf,
+    /// 60 Hz frames to run for at most
+    #[arg(short, long, default_value_t = 600)]
+    frames: u64,
+    /// Keys to hold on which frames, one "<frame> <keys>" per line, e.g. "30 5" or "40 -"
+    #[arg(short, long)]
+    input: Option<PathBuf>,
+    #[command(flatten)]
+    machine: MachineConfig,
+    /// Write the final screen to a file: .png, .pbm, or ASCII art for anything else
+    #[arg(short, long)]
+    screen: Option<PathBuf>,
+    /// Only print the status line
+    #[arg(short, long)]
+    quiet: bool,
+    /// Treat running out of frames as a failure
+    #[arg(long)]
+    expect_halt: bool,
+    /// Write a profile: hot spots, opcodes and subroutines
+    #[arg(long)]
+    profile_report: Option<PathBuf>,
+    /// Write the call stacks in the folded format flamegraph tools read
+    #[arg(long)]
+    folded: Option<PathBuf>,
+    /// Write which addresses were executed, read and written, one byte of flags per address
+    #[arg(long)]
+    coverage: Option<PathBuf>,
+    /// Add the coverage of earlier runs, as written by --coverage
+    #[arg(long)]
+    merge_coverage: Vec<PathBuf>,
+    /// Write a disassembly of the rom marked with what was executed, read and written
+    #[arg(long)]
+    listing: Option<PathBuf>,
+    /// Write a PNG of recent ram activity: writes red, reads green, executes blue
+    #[arg(long)]
+    heatmap: Option<PathBuf>,
+}
+
+enum Status {
+    Halted,
+    Errored(Chip8Error),
+    TimedOut,
+}
+
+fn main() {
+    let args = Args::parse();
+    let rom = Rom::from_path(&args.rom).unwrap_or_else(|e| exit(&args.rom, e));
+    let mut script = match args.input.as_ref() {
+        Some(path) => {
+            let text = std::fs::read_to_string(path).unwrap_or_else(|e| exit(path, e));
+            Script::parse(&text).unwrap_or_else(|e| exit(path, e))
+        }
+        None => Script::parse("").unwrap(),
+    };
+
+    let info = args
+        .machine
+        .rom_info(&rom)
+        .unwrap_or_else(|e| exit(args.machine.romdb.as_deref().unwrap_or(&args.rom), e));
+    let info = info.as_ref();
+    let mut chip8 = args
+        .machine
+        .chip8(&rom, info)
+        .unwrap_or_else(|e| exit(&args.rom, e));
+
+    let mut profiler = (args.profile_report.is_some() || args.folded.is_some()).then(Profiler::new);
+    let mut coverage = (args.coverage.is_some() || args.listing.is_some()).then(Coverage::new);
+    let mut heatmap = args.heatmap.is_some().then(Heatmap::new);
+    let mut frame = 0;
+    let status = loop {
+        if chip8.is_halted() {
+            break Status::Halted;
+        }
+        if frame == args.frames {
+            break Status::TimedOut;
+        }
+        if let Some(keys) = script.keys_at(frame) {
+            chip8.set_keypad(keys);
+        }
+        let result = chip8.frame_with(|chip8| {
+            if let Some(profiler) = profiler.as_mut() {
+                profiler.observe(chip8);
+            }
+            if let Some(coverage) = coverage.as_mut() {
+                coverage.observe(chip8);
+            }
+            if let Some(heatmap) = heatmap.as_mut() {
+                heatmap.observe(chip8);
+            }
+        });
+        if let Some(profiler) = profiler.as_mut() {
+            profiler.end_frame(&chip8);
+        }
+        if let Some(heatmap) = heatmap.as_mut() {
+            heatmap.end_frame();
+        }
+        if let Err(e) = result {
+            break Status::Errored(e);
+        }
+        frame += 1;
+    };
+
+    if let Some(path) = args.screen.as_ref() {
+        write_screen(path, &chip8).unwrap_or_else(|e| exit(path, e));
+    }
+    if let Some(profiler) = profiler.as_ref() {
+        if let Some(path) = args.profile_report.as_ref() {
+            write_profile(path, profiler).unwrap_or_else(|e| exit(path, e));
+        }
+        if let Some(path) = args.folded.as_ref() {
+            let written = File::create(path).and_then(|f| {
+                let mut w = BufWriter::new(f);
+                profiler.write_folded(&mut w)?;
+                w.flush()
+            });
+            written.unwrap_or_else(|e| exit(path, e));
+        }
+    }
+    if let Some(coverage) = coverage.as_mut() {
+        for path in args.merge_coverage.iter() {
+            let bytes = std::fs::read(path).unwrap_or_else(|e| exit(path, e));
+            coverage.merge(&Coverage::from_bitmap(&bytes).unwrap_or_else(|e| exit(path, e)));
+        }
+        if let Some(path) = args.coverage.as_ref() {
+            std::fs::write(path, coverage.to_bitmap()).unwrap_or_else(|e| exit(path, e));
+        }
+        if let Some(path) = args.listing.as_ref() {
+            let written = File::create(path).and_then(|f| {
+                let mut w = BufWriter::new(f);
+                coverage.write_listing(&mut w, rom.bytes(), chip8.profile.load_addr)?;
+                w.flush()
+            });
+            written.unwrap_or_else(|e| exit(path, e));
+        }
+    }
+    if let (Some(heatmap), Some(path)) = (heatmap.as_ref(), args.heatmap.as_ref()) {
+        let written = File::create(path).and_then(|f| {
+            let mut w = BufWriter::new(f);
+            heatmap.write_png(&mut w, 8)?;
+            w.flush()
+        });
+        written.unwrap_or_else(|e| exit(path, e));
+    }
+    if !args.quiet {
+        screenshot::write_ascii(io::stdout().lock(), &chip8.framebuffer()).ok();
+        println!();
+        print_registers(&chip8);
+        println!();
+    }
+    let code = match status {
+        Status::Halted => {
+            println!("halted at {:#05x} after {} frames", chip8.pc, frame);
+            0
+        }
+        Status::Errored(e) => {
+            println!("errored on frame {}: {}", frame, e);
+            EXIT_ERRORED
+        }
+        Status::TimedOut => {
+            println!("timed out after {} frames at {:#05x}", frame, chip8.pc);
+            if args.expect_halt {
+                EXIT_TIMED_OUT
+            } else {
+                0
+            }
+        }
+    };
+    process::exit(code);
+}
+
+fn write_screen(path: &Path, chip8: &Chip8) -> io::Result<()> {
+    let mut w = BufWriter::new(File::create(path)?);
+    let screen = chip8.framebuffer();
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("png") => screenshot::write_png(&mut w, &screen, &Palette::default(), 10)?,
+        Some("pbm") => screenshot::write_pbm(&mut w, &screen)?,
+        _ => screenshot::write_ascii(&mut w, &screen)?,
+    }
+    w.flush()
+}
+
+fn write_profile(path: &Path, profiler: &Profiler) -> io::Result<()> {
+    let mut w = BufWriter::new(File::create(path)?);
+    profiler.write_flat(&mut w)?;
+    writeln!(w)?;
+    profiler.write_call_graph(&mut w)?;
+    w.flush()
+}
+
+fn print_registers(chip8: &Chip8) {
+    println!(
+        "PC {:#05x}  I {:#05x}  SP {}  DT {}  ST {}",
+        chip8.pc, chip8.i, chip8.sp, chip8.delay_timer, chip8.sound_timer
+    );
+    for (half, regs) in chip8.v.chunks(8).enumerate() {
+        let regs: Vec<String> = regs
+            .iter()
+            .enumerate()
+            .map(|(i, v)| format!("V{:X} {:02x}", half * 8 + i, v))
+            .collect();
+        println!("{}", regs.join("  "));
+    }
+    let stack: Vec<String> = chip8
+        .call_stack()
+        .iter()
+        .map(|addr| format!("{:#05x}", addr))
+        .collect();
+    println!("stack [{}]", stack.join(" "));
+}
+
+fn exit(path: &Path, e: impl std::fmt::Display) -> ! {
+    eprintln!("{}: {}", path.display(), e);
+    process::exit(EXIT_USAGE);
+}
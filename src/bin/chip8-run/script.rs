@@ -0,0 +1,59 @@
+// Input scripts say which keys are held from which frame on, one change per line:
+//
+//     # frame  keys
+//     0        -       # nothing held
+//     30       5       # hold 5
+//     40       -
+//     90       4 6     # hold 4 and 6
+//
+// Keys are hex digits, several may share a token ("46"), and "-" or no keys at all
+// releases everything. Frames count from 0 and can't go backwards.
+pub struct Script {
+    events: Vec<(u64, [bool; 16])>,
+    next: usize,
+}
+
+impl Script {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut events: Vec<(u64, [bool; 16])> = Vec::new();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut tokens = line.split_whitespace();
+            let Some(frame) = tokens.next() else {
+                continue;
+            };
+            let frame: u64 = frame
+                .parse()
+                .map_err(|_| format!("line {}: bad frame number '{}'", n + 1, frame))?;
+            if events.last().is_some_and(|(last, _)| *last > frame) {
+                return Err(format!(
+                    "line {}: frame {} comes before the previous line's",
+                    n + 1,
+                    frame
+                ));
+            }
+            let mut keys = [false; 16];
+            for c in tokens.flat_map(str::chars).filter(|c| *c != '-') {
+                let key = c
+                    .to_digit(16)
+                    .ok_or_else(|| format!("line {}: '{}' is not a key (0-F)", n + 1, c))?;
+                keys[key as usize] = true;
+            }
+            events.push((frame, keys));
+        }
+        Ok(Self { events, next: 0 })
+    }
+
+    // The keys to hold from `frame` on, if they change on that frame.
+    pub fn keys_at(&mut self, frame: u64) -> Option<[bool; 16]> {
+        let mut keys = None;
+        while let Some((at, held)) = self.events.get(self.next) {
+            if *at > frame {
+                break;
+            }
+            keys = Some(*held);
+            self.next += 1;
+        }
+        keys
+    }
+}
@@ -0,0 +1,76 @@
+mod tui;
+
+use chip8_rs::config::{FrontendConfig, MachineConfig};
+use chip8_rs::rom::Rom;
+use clap::Parser;
+use std::path::{Path, PathBuf};
+use std::process;
+use tui::{Chip8Tui, Options};
+
+/// CHIP-8 emulator for the terminal
+///
+/// Hotkeys: F1 pause, F2 reset, F3/F4 slower/faster, F5 save state, F8 load state,
+/// Esc or Ctrl-C quit.
+#[derive(Parser)]
+#[command(name = "chip8-tui", version)]
+struct Args {
+    /// ROM to run
+    rom: PathBuf,
+    #[command(flatten)]
+    machine: MachineConfig,
+    #[command(flatten)]
+    frontend: FrontendConfig,
+    /// Hide the register panel
+    #[arg(long)]
+    no_panel: bool,
+    /// Don't ring the terminal bell for the buzzer
+    #[arg(long)]
+    mute: bool,
+}
+
+fn main() {
+    let args = Args::parse();
+    let rom = Rom::from_path(&args.rom).unwrap_or_else(|e| exit(&args.rom, e));
+    let info = args
+        .machine
+        .rom_info(&rom)
+        .unwrap_or_else(|e| exit(args.machine.romdb.as_deref().unwrap_or(&args.rom), e));
+    let info = info.as_ref();
+
+    let chip8 = args
+        .machine
+        .chip8(&rom, info)
+        .unwrap_or_else(|e| exit(&args.rom, e));
+    let keymap = args
+        .frontend
+        .keymap(&rom, info)
+        .unwrap_or_else(|e| exit(args.frontend.keymap.as_deref().unwrap_or(&args.rom), e));
+    let palette = args.frontend.palette(info);
+
+    let title = info.map_or_else(
+        || {
+            args.rom
+                .file_name()
+                .map_or("Chip8".into(), |s| s.to_string_lossy().into_owned())
+        },
+        |info| info.title.clone(),
+    );
+    let options = Options {
+        title: &title,
+        palette,
+        keymap,
+        panel: !args.no_panel,
+        mute: args.mute,
+        rom_path: &args.rom,
+    };
+    let result = Chip8Tui::new(chip8, options).and_then(|mut frontend| frontend.run());
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn exit(path: &Path, e: impl std::fmt::Display) -> ! {
+    eprintln!("{}: {}", path.display(), e);
+    process::exit(1);
+}
@@ -0,0 +1,523 @@
+use chip8_rs::chip8::Chip8;
+use chip8_rs::emulator::{AudioSink, DisplaySink, Emulator, InputEvent, InputSource};
+use chip8_rs::keymap::KeyMap;
+use chip8_rs::machine::{Framebuffer, Machine};
+use chip8_rs::palette::Palette;
+use chip8_rs::timing::Timing;
+use crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags,
+    PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+};
+use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, execute, queue};
+use std::io::{self, Stdout, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// Most terminals only report presses, repeated while a key is held. Without release
+// events a key counts as held for this many frames after its last press.
+const HOLD_FRAMES: u64 = 8;
+const PANEL_COLS: usize = 24;
+
+pub struct Options<'a> {
+    pub title: &'a str,
+    pub palette: Palette,
+    pub keymap: KeyMap,
+    // Registers, stack, timers and keypad next to the screen
+    pub panel: bool,
+    pub mute: bool,
+    // Save states are named after the rom
+    pub rom_path: &'a Path,
+}
+
+// What the panel shows of a machine besides the frontend's own status.
+pub trait Panel: Machine {
+    // Registers, stack, timers and keypad, one line each
+    fn registers(&self) -> Vec<String>;
+
+    fn speed_line(&self) -> String {
+        format!("{} Hz", self.speed() * 60)
+    }
+}
+
+impl Panel for Chip8 {
+    fn registers(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("PC {:03X}   I {:03X}", self.pc, self.i),
+            format!("DT {:<3}   ST {:<3}", self.delay_timer, self.sound_timer),
+            String::new(),
+        ];
+        for (row, regs) in self.v.chunks(4).enumerate() {
+            let regs: Vec<String> = regs
+                .iter()
+                .enumerate()
+                .map(|(i, v)| format!("V{:X} {:02X}", row * 4 + i, v))
+                .collect();
+            lines.push(regs.join(" "));
+        }
+        lines.push(String::new());
+        lines.push(format!("SP {}", self.sp));
+        for addrs in self.call_stack().chunks(4) {
+            let addrs: Vec<String> = addrs.iter().map(|a| format!("{:03X}", a)).collect();
+            lines.push(format!("   {}", addrs.join(" ")));
+        }
+        lines.push(String::new());
+        let keys: String = (0..16)
+            .map(|k| {
+                if self.keypad[k] {
+                    format!("{:X}", k)
+                } else {
+                    ".".into()
+                }
+            })
+            .collect();
+        lines.push(format!("Keys {}", keys));
+        lines
+    }
+
+    fn speed_line(&self) -> String {
+        match self.timing {
+            Timing::Instructions => format!("{} Hz", self.tick_rate * 60),
+            Timing::Vip => "VIP timing".into(),
+        }
+    }
+}
+
+// The terminal; the screen is drawn inside a border, two pixels per cell, and follows
+// the size of the machine's screen.
+pub struct TuiDisplay {
+    out: Stdout,
+    title: String,
+    palette: Palette,
+    panel: bool,
+    // The terminal reports key releases (kitty keyboard protocol)
+    releases: bool,
+    size: (u16, u16),
+    // Cells the last screen took up, border included
+    cells: (u16, u16),
+    // What's on the terminal, so only changes get redrawn; None redraws everything
+    shown: Option<Vec<u8>>,
+    shown_panel: Vec<String>,
+}
+
+impl TuiDisplay {
+    fn new(title: &str, palette: Palette, panel: bool, screen: &Framebuffer) -> io::Result<Self> {
+        let mut display = Self {
+            out: io::stdout(),
+            title: title.to_string(),
+            palette,
+            panel,
+            releases: false,
+            size: terminal::size()?,
+            cells: cells(screen),
+            shown: None,
+            shown_panel: Vec::new(),
+        };
+        // Dropping `display` puts the terminal back if anything below fails
+        terminal::enable_raw_mode()?;
+        execute!(display.out, terminal::EnterAlternateScreen, cursor::Hide)?;
+        if terminal::supports_keyboard_enhancement().unwrap_or(false) {
+            execute!(
+                display.out,
+                PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+            )?;
+            display.releases = true;
+        }
+        display.invalidate()?;
+        Ok(display)
+    }
+
+    fn fits(&self) -> bool {
+        self.size.0 >= self.cells.0 && self.size.1 >= self.cells.1
+    }
+
+    fn resize(&mut self, size: (u16, u16)) -> io::Result<()> {
+        self.size = size;
+        self.invalidate()
+    }
+
+    // Clears the terminal so the next draw starts from scratch
+    fn invalidate(&mut self) -> io::Result<()> {
+        self.shown = None;
+        self.shown_panel.clear();
+        queue!(self.out, ResetColor, terminal::Clear(ClearType::All))?;
+        if !self.fits() {
+            let msg = format!(
+                "terminal too small, needs {}x{}",
+                self.cells.0, self.cells.1
+            );
+            queue!(self.out, cursor::MoveTo(0, 0), Print(msg))?;
+        }
+        self.out.flush()
+    }
+
+    fn draw(&mut self, screen: &Framebuffer) -> io::Result<()> {
+        if cells(screen) != self.cells {
+            self.cells = cells(screen);
+            self.invalidate()?;
+        }
+        if !self.fits() {
+            return Ok(());
+        }
+        if self.shown.is_none() {
+            self.draw_border(screen.width)?;
+        }
+
+        // Upper half block: foreground is the top pixel, background the bottom one,
+        // which is off below the last row of an odd height
+        let pixel = |pixels: &[u8], x: usize, y: usize| {
+            if y < screen.height {
+                pixels[y * screen.width + x]
+            } else {
+                0
+            }
+        };
+        let mut colors = None;
+        for row in 0..screen.height.div_ceil(2) {
+            let mut cursor_col = None;
+            for col in 0..screen.width {
+                let cell = (
+                    pixel(screen.pixels, col, row * 2),
+                    pixel(screen.pixels, col, row * 2 + 1),
+                );
+                if let Some(shown) = &self.shown {
+                    if (pixel(shown, col, row * 2), pixel(shown, col, row * 2 + 1)) == cell {
+                        continue;
+                    }
+                }
+                if cursor_col != Some(col) {
+                    queue!(self.out, cursor::MoveTo(col as u16 + 1, row as u16 + 1))?;
+                }
+                let cell = (self.palette.color(cell.0), self.palette.color(cell.1));
+                if colors != Some(cell) {
+                    queue!(
+                        self.out,
+                        SetForegroundColor(rgb(cell.0)),
+                        SetBackgroundColor(rgb(cell.1))
+                    )?;
+                    colors = Some(cell);
+                }
+                queue!(self.out, Print('▀'))?;
+                cursor_col = Some(col + 1);
+            }
+        }
+        if colors.is_some() {
+            queue!(self.out, ResetColor)?;
+        }
+        self.shown = Some(screen.pixels.to_vec());
+        self.out.flush()
+    }
+
+    fn draw_border(&mut self, width: usize) -> io::Result<()> {
+        let title: String = format!(" {} ", self.title)
+            .chars()
+            .take(width.saturating_sub(2))
+            .collect();
+        let top = format!(
+            "┌─{}{}┐",
+            title,
+            "─".repeat(width.saturating_sub(1 + title.chars().count()))
+        );
+        queue!(self.out, cursor::MoveTo(0, 0), Print(top))?;
+        for row in 1..self.cells.1 - 1 {
+            queue!(
+                self.out,
+                cursor::MoveTo(0, row),
+                Print('│'),
+                cursor::MoveTo(self.cells.0 - 1, row),
+                Print('│')
+            )?;
+        }
+        let bottom = format!("└{}┘", "─".repeat(width));
+        queue!(self.out, cursor::MoveTo(0, self.cells.1 - 1), Print(bottom))
+    }
+
+    fn draw_panel(&mut self, lines: Vec<String>) -> io::Result<()> {
+        if !self.panel || !self.fits() || self.size.0 as usize <= self.cells.0 as usize + PANEL_COLS
+        {
+            return Ok(());
+        }
+        for (row, line) in lines.iter().enumerate().take(self.size.1 as usize) {
+            if self.shown_panel.get(row) != Some(line) {
+                queue!(
+                    self.out,
+                    cursor::MoveTo(self.cells.0 + 1, row as u16),
+                    Print(format!("{:<w$.w$}", line, w = PANEL_COLS))
+                )?;
+            }
+        }
+        self.shown_panel = lines;
+        self.out.flush()
+    }
+}
+
+impl DisplaySink for TuiDisplay {
+    fn present(&mut self, screen: &Framebuffer, _paused: bool) {
+        // The panel shows when it's paused
+        let _ = self.draw(screen);
+    }
+}
+
+impl Drop for TuiDisplay {
+    fn drop(&mut self) {
+        if self.releases {
+            let _ = execute!(self.out, PopKeyboardEnhancementFlags);
+        }
+        let _ = execute!(
+            self.out,
+            ResetColor,
+            cursor::Show,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+// The terminal bell, rung when the buzzer starts
+pub struct TuiAudio {
+    out: Stdout,
+    mute: bool,
+    on: bool,
+}
+
+impl AudioSink for TuiAudio {
+    fn set_buzzer(&mut self, on: bool) {
+        if on && !self.on && !self.mute {
+            let _ = execute!(self.out, Print('\x07'));
+        }
+        self.on = on;
+    }
+}
+
+// The keyboard
+pub struct TuiInput {
+    keymap: KeyMap,
+    // The terminal reports key releases, see `TuiDisplay`
+    releases: bool,
+    // Frame at which each held CHIP-8 key is let go
+    held: [Option<u64>; 16],
+    frame: u64,
+    // Function keys pressed since the frontend last looked, including the ones that
+    // also went to the emulator as events
+    hotkeys: Vec<KeyCode>,
+    // The terminal's new size, for the frontend to redraw at
+    resized: Option<(u16, u16)>,
+}
+
+impl InputSource for TuiInput {
+    fn poll(&mut self) -> Vec<InputEvent> {
+        let mut input = Vec::new();
+        loop {
+            let event = match event::poll(Duration::ZERO)
+                .and_then(|ready| ready.then(event::read).transpose())
+            {
+                Ok(Some(event)) => event,
+                Ok(None) => break,
+                Err(_) => {
+                    input.push(InputEvent::Quit);
+                    break;
+                }
+            };
+            match event {
+                Event::Key(key) => self.key(key, &mut input),
+                Event::Resize(cols, rows) => self.resized = Some((cols, rows)),
+                _ => (),
+            }
+        }
+
+        self.frame += 1;
+        for (key, held) in self.held.iter_mut().enumerate() {
+            if held.is_some_and(|until| until <= self.frame) {
+                *held = None;
+                input.push(InputEvent::KeyUp(key));
+            }
+        }
+        input
+    }
+}
+
+impl TuiInput {
+    fn key(&mut self, event: KeyEvent, input: &mut Vec<InputEvent>) {
+        if event.kind == KeyEventKind::Press {
+            let ctrl_c =
+                event.code == KeyCode::Char('c') && event.modifiers.contains(KeyModifiers::CONTROL);
+            if event.code == KeyCode::Esc || ctrl_c {
+                input.push(InputEvent::Quit);
+                return;
+            }
+            if let Some(hotkey) = self.hotkey(event.code) {
+                input.extend(hotkey);
+                return;
+            }
+        }
+        let Some(key) = key_name(event.code).and_then(|name| self.keymap.key(&name)) else {
+            return;
+        };
+        let key = key as usize;
+        if event.kind == KeyEventKind::Release {
+            self.held[key] = None;
+            input.push(InputEvent::KeyUp(key));
+        } else {
+            if self.held[key].is_none() {
+                input.push(InputEvent::KeyDown(key));
+            }
+            self.held[key] = Some(if self.releases {
+                u64::MAX
+            } else {
+                self.frame + HOLD_FRAMES
+            });
+        }
+    }
+
+    // Emulator controls, the event for the emulator if there is one; None if `code`
+    // isn't one, so it can go to the CHIP-8 keypad.
+    fn hotkey(&mut self, code: KeyCode) -> Option<Option<InputEvent>> {
+        let hotkey = match code {
+            KeyCode::F(1) => Some(InputEvent::Pause),
+            KeyCode::F(2) => Some(InputEvent::Reset),
+            KeyCode::F(3) => Some(InputEvent::Slower),
+            KeyCode::F(4) => Some(InputEvent::Faster),
+            KeyCode::F(5) | KeyCode::F(8) => None,
+            _ => return None,
+        };
+        self.hotkeys.push(code);
+        Some(hotkey)
+    }
+}
+
+pub struct Chip8Tui<M: Panel> {
+    emulator: Emulator<TuiDisplay, TuiAudio, TuiInput, M>,
+    host: Host,
+}
+
+// What the frontend does itself between frames: the panel and save states
+struct Host {
+    status: String,
+    state_path: PathBuf,
+}
+
+impl<M: Panel> Chip8Tui<M> {
+    pub fn new(machine: M, options: Options) -> io::Result<Self> {
+        let display = TuiDisplay::new(
+            options.title,
+            options.palette,
+            options.panel,
+            &machine.framebuffer(),
+        )?;
+        let input = TuiInput {
+            keymap: options.keymap,
+            releases: display.releases,
+            held: [None; 16],
+            frame: 0,
+            hotkeys: Vec::new(),
+            resized: None,
+        };
+        let audio = TuiAudio {
+            out: io::stdout(),
+            mute: options.mute,
+            on: false,
+        };
+        Ok(Self {
+            emulator: Emulator::new(machine, display, audio, input),
+            host: Host {
+                status: String::new(),
+                state_path: options.rom_path.with_extension("state"),
+            },
+        })
+    }
+
+    // One frame per 60 Hz tick, reading input in between. An emulator error pauses on
+    // the failing instruction, and F2 starts over.
+    pub fn run(&mut self) -> io::Result<()> {
+        let host = &mut self.host;
+        let mut result = Ok(());
+        self.emulator.run_with(|emulator| {
+            if let Err(e) = host.after_frame(emulator) {
+                result = Err(e);
+                emulator.quit = true;
+            }
+        });
+        result
+    }
+}
+
+impl Host {
+    fn after_frame<M: Panel>(
+        &mut self,
+        emulator: &mut Emulator<TuiDisplay, TuiAudio, TuiInput, M>,
+    ) -> io::Result<()> {
+        if let Some(e) = emulator.error.take() {
+            self.status = e.to_string();
+        }
+        let hotkeys: Vec<KeyCode> = emulator.input.hotkeys.drain(..).collect();
+        for code in hotkeys {
+            self.hotkey(&mut emulator.machine, code);
+        }
+        if let Some(size) = emulator.input.resized.take() {
+            emulator.display.resize(size)?;
+            emulator.display.draw(&emulator.machine.framebuffer())?;
+        }
+
+        let mut lines = emulator.machine.registers();
+        lines.push(format!(
+            "{}{}",
+            emulator.machine.speed_line(),
+            if emulator.paused { "  paused" } else { "" }
+        ));
+        lines.push(self.status.clone());
+        emulator.display.draw_panel(lines)
+    }
+
+    // Pause, reset and speed were handled by the emulator already
+    fn hotkey<M: Machine>(&mut self, machine: &mut M, code: KeyCode) {
+        match code {
+            KeyCode::F(2) => self.status = "reset".into(),
+            KeyCode::F(5) => {
+                self.status = match std::fs::write(&self.state_path, machine.save_state()) {
+                    Ok(()) => "saved state".into(),
+                    Err(e) => e.to_string(),
+                }
+            }
+            KeyCode::F(8) => {
+                let loaded = std::fs::read(&self.state_path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|state| machine.load_state(&state).map_err(|e| e.to_string()));
+                self.status = match loaded {
+                    Ok(()) => "loaded state".into(),
+                    Err(e) => e,
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+// Cells a screen takes up inside its border, two pixels per cell
+fn cells(screen: &Framebuffer) -> (u16, u16) {
+    (
+        screen.width as u16 + 2,
+        screen.height.div_ceil(2) as u16 + 2,
+    )
+}
+
+fn rgb([r, g, b]: [u8; 3]) -> Color {
+    Color::Rgb { r, g, b }
+}
+
+// Terminal keys by their SDL names, which is what key maps use
+fn key_name(code: KeyCode) -> Option<String> {
+    let name = match code {
+        KeyCode::Char(' ') => "Space",
+        KeyCode::Char(c) => return Some(c.to_string()),
+        KeyCode::Up => "Up",
+        KeyCode::Down => "Down",
+        KeyCode::Left => "Left",
+        KeyCode::Right => "Right",
+        KeyCode::Enter => "Return",
+        KeyCode::Tab => "Tab",
+        KeyCode::Backspace => "Backspace",
+        _ => return None,
+    };
+    Some(name.to_string())
+}
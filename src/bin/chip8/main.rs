@@ -0,0 +1,74 @@
+mod sdl;
+
+use chip8_rs::config::{FrontendConfig, MachineConfig};
+use chip8_rs::rom::Rom;
+use clap::Parser;
+use sdl::{Chip8Sdl, Options};
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// CHIP-8 emulator
+///
+/// Hotkeys: F1 pause, F2 reset, F3/F4 slower/faster, F5 save state, F8 load state,
+/// F9 record GIF, F10 record GIF with raw frames and WAV, F12 screenshot, Esc quit.
+#[derive(Parser)]
+#[command(name = "chip8", version)]
+struct Args {
+    /// ROM to run
+    rom: PathBuf,
+    #[command(flatten)]
+    machine: MachineConfig,
+    #[command(flatten)]
+    frontend: FrontendConfig,
+    /// Window pixels per CHIP-8 pixel
+    #[arg(long, default_value_t = 10)]
+    scale: u32,
+    #[arg(long)]
+    fullscreen: bool,
+    #[arg(long)]
+    mute: bool,
+}
+
+fn main() {
+    let args = Args::parse();
+    let rom = Rom::from_path(&args.rom).unwrap_or_else(|e| exit(&args.rom, e));
+    let info = args
+        .machine
+        .rom_info(&rom)
+        .unwrap_or_else(|e| exit(args.machine.romdb.as_deref().unwrap_or(&args.rom), e));
+    let info = info.as_ref();
+    if let Some(info) = info {
+        println!("{} ({})", info.title, info.platform);
+    }
+
+    let chip8 = args
+        .machine
+        .chip8(&rom, info)
+        .unwrap_or_else(|e| exit(&args.rom, e));
+    let keymap = args
+        .frontend
+        .keymap(&rom, info)
+        .unwrap_or_else(|e| exit(args.frontend.keymap.as_deref().unwrap_or(&args.rom), e));
+    let palette = args.frontend.palette(info);
+
+    let title = info.map_or("Chip8", |info| info.title.as_str());
+    let options = Options {
+        title,
+        scale: args.scale,
+        palette,
+        keymap,
+        fullscreen: args.fullscreen,
+        mute: args.mute,
+        rom_path: &args.rom,
+    };
+    let mut frontend = Chip8Sdl::new(chip8, options).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+    frontend.run();
+}
+
+fn exit(path: &Path, e: impl std::fmt::Display) -> ! {
+    eprintln!("{}: {}", path.display(), e);
+    process::exit(1);
+}
@@ -0,0 +1,398 @@
+use chip8_rs::emulator::{AudioSink, DisplaySink, Emulator, InputEvent, InputSource};
+use chip8_rs::keymap::KeyMap;
+use chip8_rs::machine::{Framebuffer, Machine};
+use chip8_rs::palette::Palette;
+use chip8_rs::recorder::Recorder;
+use chip8_rs::screenshot;
+use sdl2::audio::{AudioCallback, AudioDevice, AudioSpecDesired};
+use sdl2::controller::GameController;
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::rect::Rect;
+use sdl2::render::Canvas;
+use sdl2::video::Window;
+use sdl2::{pixels, EventPump, GameControllerSubsystem};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const BUZZER_HZ: f32 = 440.0;
+
+pub struct Options<'a> {
+    pub title: &'a str,
+    pub scale: u32,
+    pub palette: Palette,
+    pub keymap: KeyMap,
+    pub fullscreen: bool,
+    pub mute: bool,
+    // Save states and screenshots are named after the rom
+    pub rom_path: &'a Path,
+}
+
+struct SquareWave {
+    phase_inc: f32,
+    phase: f32,
+    volume: f32,
+}
+
+impl AudioCallback for SquareWave {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [f32]) {
+        for x in out.iter_mut() {
+            *x = if self.phase < 0.5 {
+                self.volume
+            } else {
+                -self.volume
+            };
+            self.phase = (self.phase + self.phase_inc) % 1.0;
+        }
+    }
+}
+
+// The window; it follows the size of the machine's screen.
+pub struct SdlDisplay {
+    canvas: Canvas<Window>,
+    title: String,
+    palette: Palette,
+    paused: bool,
+}
+
+impl DisplaySink for SdlDisplay {
+    fn present(&mut self, screen: &Framebuffer, paused: bool) {
+        if paused != self.paused {
+            self.paused = paused;
+            let title = if paused {
+                format!("{} (paused)", self.title)
+            } else {
+                self.title.clone()
+            };
+            let _ = self.canvas.window_mut().set_title(&title);
+        }
+        let size = (screen.width as u32, screen.height as u32);
+        if self.canvas.logical_size() != size {
+            let _ = self.canvas.set_logical_size(size.0, size.1);
+        }
+        let [r, g, b] = self.palette.background;
+        self.canvas.set_draw_color(pixels::Color::RGB(r, g, b));
+        self.canvas.clear();
+        let [r, g, b] = self.palette.foreground;
+        self.canvas.set_draw_color(pixels::Color::RGB(r, g, b));
+        for (y, row) in screen.rows().enumerate() {
+            for (x, col) in row.iter().enumerate() {
+                if *col != 0 {
+                    let _ = self.canvas.fill_rect(Rect::new(x as i32, y as i32, 1, 1));
+                }
+            }
+        }
+        self.canvas.present();
+    }
+}
+
+pub struct SdlAudio {
+    // None when muted
+    beeper: Option<AudioDevice<SquareWave>>,
+}
+
+impl AudioSink for SdlAudio {
+    fn set_buzzer(&mut self, on: bool) {
+        if let Some(beeper) = self.beeper.as_ref() {
+            if on {
+                beeper.resume();
+            } else {
+                beeper.pause();
+            }
+        }
+    }
+}
+
+// Keyboard and controllers
+pub struct SdlInput {
+    events: EventPump,
+    controller_subsys: GameControllerSubsystem,
+    // Opened controllers only send events while they're kept alive
+    controllers: Vec<GameController>,
+    keymap: KeyMap,
+    // CHIP-8 key currently held by each (controller, axis)
+    axes: HashMap<(u32, String), u8>,
+    // Function keys pressed since the frontend last looked, including the ones that
+    // also went to the emulator as events
+    hotkeys: Vec<Keycode>,
+}
+
+impl InputSource for SdlInput {
+    fn poll(&mut self) -> Vec<InputEvent> {
+        let mut input = Vec::new();
+        let events: Vec<Event> = self.events.poll_iter().collect();
+        for event in events {
+            match event {
+                Event::Quit { .. }
+                | Event::KeyDown {
+                    keycode: Some(Keycode::Escape),
+                    ..
+                } => input.push(InputEvent::Quit),
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    repeat: false,
+                    ..
+                } => {
+                    if let Some(event) = self.host_key_down(keycode) {
+                        input.push(event);
+                    }
+                }
+                Event::KeyUp {
+                    keycode: Some(keycode),
+                    ..
+                } => {
+                    if let Some(key) = self.keymap.key(&keycode.name()) {
+                        input.push(InputEvent::KeyUp(key as usize));
+                    }
+                }
+                Event::ControllerDeviceAdded { which, .. } => {
+                    match self.controller_subsys.open(which) {
+                        Ok(controller) => {
+                            println!("controller connected: {}", controller.name());
+                            self.controllers.push(controller);
+                        }
+                        Err(e) => eprintln!("could not open controller: {}", e),
+                    }
+                }
+                Event::ControllerDeviceRemoved { which, .. } => {
+                    self.controllers.retain(|c| c.instance_id() != which);
+                }
+                Event::ControllerButtonDown { button, .. } => {
+                    if let Some(key) = self.keymap.button(&button.string()) {
+                        input.push(InputEvent::KeyDown(key as usize));
+                    }
+                }
+                Event::ControllerButtonUp { button, .. } => {
+                    if let Some(key) = self.keymap.button(&button.string()) {
+                        input.push(InputEvent::KeyUp(key as usize));
+                    }
+                }
+                Event::ControllerAxisMotion {
+                    which, axis, value, ..
+                } => {
+                    let axis = axis.string();
+                    let key = self.keymap.axis(&axis, value);
+                    let held = self.axes.get(&(which, axis.clone())).copied();
+                    if key != held {
+                        if let Some(held) = held {
+                            input.push(InputEvent::KeyUp(held as usize));
+                            self.axes.remove(&(which, axis.clone()));
+                        }
+                        if let Some(key) = key {
+                            input.push(InputEvent::KeyDown(key as usize));
+                            self.axes.insert((which, axis), key);
+                        }
+                    }
+                }
+                _ => (),
+            };
+        }
+        input
+    }
+}
+
+impl SdlInput {
+    fn host_key_down(&mut self, keycode: Keycode) -> Option<InputEvent> {
+        let hotkey = match keycode {
+            Keycode::F1 => Some(InputEvent::Pause),
+            Keycode::F2 => Some(InputEvent::Reset),
+            Keycode::F3 => Some(InputEvent::Slower),
+            Keycode::F4 => Some(InputEvent::Faster),
+            Keycode::F5 | Keycode::F8 | Keycode::F9 | Keycode::F10 | Keycode::F12 => None,
+            _ => {
+                let key = self.keymap.key(&keycode.name())?;
+                return Some(InputEvent::KeyDown(key as usize));
+            }
+        };
+        self.hotkeys.push(keycode);
+        hotkey
+    }
+}
+
+pub struct Chip8Sdl<M: Machine> {
+    emulator: Emulator<SdlDisplay, SdlAudio, SdlInput, M>,
+    host: Host,
+}
+
+// What the frontend does itself between frames: save states, screenshots, recordings
+struct Host {
+    recorder: Option<Recorder>,
+    // F9 records a GIF, F10 also dumps raw frames and a WAV of the buzzer
+    record_raw: bool,
+    state_path: PathBuf,
+    screenshot_stem: String,
+}
+
+impl<M: Machine> Chip8Sdl<M> {
+    pub fn new(machine: M, options: Options) -> Result<Self, String> {
+        let sdl = sdl2::init()?;
+        let events = sdl.event_pump()?;
+        let controller_subsys = sdl.game_controller()?;
+        let video_subsys = sdl.video()?;
+        let screen = machine.framebuffer();
+        let (width, height) = (screen.width as u32, screen.height as u32);
+        let mut window =
+            video_subsys.window(options.title, width * options.scale, height * options.scale);
+        window.position_centered().opengl();
+        if options.fullscreen {
+            window.fullscreen_desktop();
+        }
+        let window = window.build().map_err(|e| e.to_string())?;
+        let mut canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
+        // Draw in CHIP-8 pixels and let SDL scale (and letterbox when fullscreen)
+        canvas
+            .set_logical_size(width, height)
+            .map_err(|e| e.to_string())?;
+
+        let beeper = if options.mute {
+            None
+        } else {
+            let audio_subsys = sdl.audio()?;
+            let desired = AudioSpecDesired {
+                freq: Some(44100),
+                channels: Some(1),
+                samples: None,
+            };
+            let device = audio_subsys.open_playback(None, &desired, |spec| SquareWave {
+                phase_inc: BUZZER_HZ / spec.freq as f32,
+                phase: 0.0,
+                volume: 0.1,
+            })?;
+            Some(device)
+        };
+
+        let stem = options
+            .rom_path
+            .file_stem()
+            .map_or("chip8".into(), |s| s.to_string_lossy().into_owned());
+
+        let display = SdlDisplay {
+            canvas,
+            title: options.title.to_string(),
+            palette: options.palette,
+            paused: false,
+        };
+        let input = SdlInput {
+            events,
+            controller_subsys,
+            controllers: Vec::new(),
+            keymap: options.keymap,
+            axes: HashMap::new(),
+            hotkeys: Vec::new(),
+        };
+        Ok(Self {
+            emulator: Emulator::new(machine, display, SdlAudio { beeper }, input),
+            host: Host {
+                recorder: None,
+                record_raw: false,
+                state_path: options.rom_path.with_extension("state"),
+                screenshot_stem: stem,
+            },
+        })
+    }
+
+    // One CHIP-8 frame per 60 Hz display frame; recordings capture at the same boundary.
+    // An emulator error pauses, and F2 starts over.
+    pub fn run(&mut self) {
+        let host = &mut self.host;
+        self.emulator
+            .run_with(|emulator| host.after_frame(emulator));
+    }
+}
+
+impl Host {
+    fn after_frame<M: Machine>(
+        &mut self,
+        emulator: &mut Emulator<SdlDisplay, SdlAudio, SdlInput, M>,
+    ) {
+        if let Some(e) = emulator.error.take() {
+            eprintln!("{}", e);
+        }
+        let hotkeys: Vec<Keycode> = emulator.input.hotkeys.drain(..).collect();
+        for keycode in hotkeys {
+            self.hotkey(emulator, keycode);
+        }
+        if !emulator.paused {
+            if let Some(recorder) = self.recorder.as_mut() {
+                recorder.capture_frame(&emulator.machine);
+                if recorder.is_full() {
+                    println!("recording reached its length limit");
+                    self.toggle_recording(emulator.display.palette, self.record_raw);
+                }
+            }
+        }
+    }
+
+    // Pause, reset and speed were handled by the emulator already
+    fn hotkey<M: Machine>(
+        &mut self,
+        emulator: &mut Emulator<SdlDisplay, SdlAudio, SdlInput, M>,
+        keycode: Keycode,
+    ) {
+        let machine = &mut emulator.machine;
+        match keycode {
+            Keycode::F2 => println!("reset"),
+            Keycode::F3 | Keycode::F4 => println!("{} Hz", machine.speed() * 60),
+            Keycode::F5 => match std::fs::write(&self.state_path, machine.save_state()) {
+                Ok(()) => println!("saved state to {}", self.state_path.display()),
+                Err(e) => eprintln!("{}: {}", self.state_path.display(), e),
+            },
+            Keycode::F8 => {
+                let loaded = std::fs::read(&self.state_path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|state| machine.load_state(&state).map_err(|e| e.to_string()));
+                match loaded {
+                    Ok(()) => println!("loaded state from {}", self.state_path.display()),
+                    Err(e) => eprintln!("{}: {}", self.state_path.display(), e),
+                }
+            }
+            Keycode::F9 => self.toggle_recording(emulator.display.palette, false),
+            Keycode::F10 => self.toggle_recording(emulator.display.palette, true),
+            Keycode::F12 => {
+                let path = format!("{}_{}.png", self.screenshot_stem, timestamp());
+                let palette = &emulator.display.palette;
+                match screenshot::save_png(&path, &machine.framebuffer(), palette, 10) {
+                    Ok(()) => println!("saved {}", path),
+                    Err(e) => eprintln!("{}: {}", path, e),
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn toggle_recording(&mut self, palette: Palette, raw: bool) {
+        match self.recorder.take() {
+            None => {
+                println!("recording started");
+                self.recorder = Some(Recorder::with_palette(palette));
+                self.record_raw = raw;
+            }
+            Some(recorder) => {
+                let stem = format!("{}_{}", self.screenshot_stem, timestamp());
+                match recorder.save(&stem, 4, self.record_raw) {
+                    Ok(()) if self.record_raw => {
+                        let (width, height) = recorder.raw_size();
+                        println!(
+                            "saved {} frames to {}.gif, and {}x{} raw frames",
+                            recorder.frame_count(),
+                            stem,
+                            width,
+                            height
+                        );
+                    }
+                    Ok(()) => println!("saved {} frames to {}.gif", recorder.frame_count(), stem),
+                    Err(e) => eprintln!("failed to save recording: {}", e),
+                }
+            }
+        }
+    }
+}
+
+fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
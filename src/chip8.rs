@@ -1,10 +1,22 @@
+use crate::instruction::Instruction;
+use crate::profile::Profile;
+use crate::quirks::Quirks;
+use crate::rom::{Rom, RomError};
+use crate::romdb::{RomDatabase, RomInfo};
+use crate::timing::{self, Timing};
+use std::fmt;
+use std::ops::Range;
 use std::path::Path;
-use rand::Rng;
 
 pub const CHIP8_SCREEN_WIDTH: usize = 64;
 pub const CHIP8_SCREEN_HEIGHT: usize = 32;
 pub const CHIP8_RAM: usize = 4096;
 pub const CHIP8_START_ADDR: usize = 0x200;
+// Return addresses the stack holds unless the profile says otherwise, so the deepest
+// nesting of 2nnn calls
+pub const CHIP8_STACK_DEPTH: usize = 16;
+pub const CHIP8_MAX_STACK_DEPTH: usize = 64;
+pub const CHIP8_FONT_ADDR: usize = 0x50;
 pub const CHIP8_FONTSET: [u8; 80] = [
     0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
     0x20, 0x60, 0x20, 0x20, 0x70, // 1
@@ -24,9 +36,47 @@ pub const CHIP8_FONTSET: [u8; 80] = [
     0xF0, 0x80, 0xF0, 0x80, 0x80, // F
 ];
 
+// Something the running program did that the machine can't carry out. `pc` is the
+// address of the instruction, which is left in place: running again fails the same way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chip8Error {
+    StackOverflow { pc: usize },
+    StackUnderflow { pc: usize },
+    InvalidOpcode { pc: usize, opcode: u16 },
+    // The pc itself ran off the end of ram
+    PcOutOfRange { pc: usize },
+    // An instruction reading or writing ram from I ran off the end
+    MemoryOutOfRange { pc: usize, i: usize },
+}
+
+impl fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Chip8Error::StackOverflow { pc } => write!(f, "stack overflow at {:#05x}", pc),
+            Chip8Error::StackUnderflow { pc } => {
+                write!(f, "return with an empty stack at {:#05x}", pc)
+            }
+            Chip8Error::InvalidOpcode { pc, opcode } => {
+                write!(f, "invalid opcode {:04x} at {:#05x}", opcode, pc)
+            }
+            Chip8Error::PcOutOfRange { pc } => write!(f, "pc ran out of ram at {:#05x}", pc),
+            Chip8Error::MemoryOutOfRange { pc, i } => {
+                write!(
+                    f,
+                    "access past the end of ram from I={:#05x} at {:#05x}",
+                    i, pc
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for Chip8Error {}
+
 #[derive(Clone, Copy)]
 pub struct Chip8 {
-    pub ram: [u8; CHIP8_RAM],
+    // Read with `ram`, written with `write_ram` so the decoded instructions keep up
+    ram: [u8; CHIP8_RAM],
     pub v: [u8; 16],
     pub i: usize,
     pub pc: usize,
@@ -34,20 +84,59 @@ pub struct Chip8 {
     pub screen: [[u8; CHIP8_SCREEN_WIDTH]; CHIP8_SCREEN_HEIGHT],
     pub delay_timer: u8,
     pub sound_timer: u8,
-    pub stack: [usize; 16],
+    // Return addresses, unless the profile keeps them in ram; see `stack_slot`
+    pub stack: [usize; CHIP8_MAX_STACK_DEPTH],
     pub keypad: [bool; 16],
+    // Keys that went down/up since the last frame
+    pub pressed: [bool; 16],
+    pub released: [bool; 16],
+    // While Fx0A waits: the keys pressed since it started, any of which ends the wait
+    // when released. Keys already held when it started don't count.
+    key_wait: Option<[bool; 16]>,
+    pub quirks: Quirks,
+    pub profile: Profile,
+    // Instructions executed per 60 Hz frame by `run_frame`
+    pub tick_rate: usize,
+    // Whether a frame runs `tick_rate` instructions or VIP machine cycles
+    pub timing: Timing,
+    // Keep instructions decoded per address instead of decoding them on every run.
+    // Only off for comparing against, see examples/decode_cache.rs
+    pub decode_cache: bool,
+    // A sprite was drawn since the last timer tick (for the display wait quirk)
+    drawn: bool,
+    // With VIP timing, cycles left in this frame; below 0 if the last instruction of
+    // the previous frame overran it
+    cycle_budget: i64,
+    // xorshift64* state behind Cxkk, never 0
+    rng: u64,
+    // Ram as it was after the font and rom were loaded, restored by `reset`
+    initial_ram: [u8; CHIP8_RAM],
+    // Instruction starting at each address, once it ran. Every write to ram drops the
+    // entries it touches, see `invalidate_decoded`.
+    decoded: [Option<Instruction>; CHIP8_RAM],
+    // Start and end of the ram written since `take_written`, all of it in one range
+    written: Option<(usize, usize)>,
+}
+
+impl Default for Chip8 {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Chip8 {
     pub fn new() -> Self {
+        Self::with_profile(Profile::default())
+    }
+
+    pub fn with_profile(profile: Profile) -> Self {
+        let profile = profile.fit();
         let mut ram = [0; 4096];
-        CHIP8_FONTSET
-            .iter()
-            .enumerate()
-            .for_each(|(i, font)| ram[i + 0x50] = *font);
+        ram[profile.font_addr..profile.font_addr + profile.font.len()]
+            .copy_from_slice(&profile.font);
 
         Self {
-            pc: CHIP8_START_ADDR,
+            pc: profile.start_pc,
             i: 0,
             sp: 0,
             ram,
@@ -55,80 +144,385 @@ impl Chip8 {
             screen: [[0; CHIP8_SCREEN_WIDTH]; CHIP8_SCREEN_HEIGHT],
             delay_timer: 0,
             sound_timer: 0,
-            stack: [0; 16],
+            stack: [0; CHIP8_MAX_STACK_DEPTH],
             keypad: [false; 16],
+            pressed: [false; 16],
+            released: [false; 16],
+            key_wait: None,
+            quirks: Quirks::default(),
+            profile,
+            tick_rate: 1,
+            timing: Timing::default(),
+            decode_cache: true,
+            drawn: false,
+            cycle_budget: 0,
+            rng: seed_rng(rand::random()),
+            initial_ram: ram,
+            decoded: [None; CHIP8_RAM],
+            written: Some((0, CHIP8_RAM)),
         }
     }
 
-    pub fn load_rom(&mut self, path: impl AsRef<Path>) {
-        use std::fs::File;
-        use std::io::Read;
-        let mut f = File::open(path).expect("file not found");
-        f.read(&mut self.ram[0x200..]).unwrap();
+    // Loads the rom and, if it is in the bundled rom database, applies its recommended
+    // quirks and tick rate. Unknown roms get the defaults, as with `new`.
+    pub fn from_known_rom(rom: &Rom) -> Result<Self, RomError> {
+        let mut chip8 = Self::new();
+        chip8.load(rom)?;
+        if let Some(info) = RomDatabase::bundled().lookup(rom) {
+            chip8.apply_rom_info(info);
+        }
+        Ok(chip8)
+    }
+
+    pub fn apply_rom_info(&mut self, info: &RomInfo) {
+        self.quirks = info.quirks;
+        if let Some(tick_rate) = info.tick_rate {
+            self.tick_rate = tick_rate;
+        }
+    }
+
+    pub fn load_rom(&mut self, path: impl AsRef<Path>) -> Result<Rom, RomError> {
+        let rom = Rom::from_path(path)?;
+        self.load(&rom)?;
+        Ok(rom)
+    }
+
+    pub fn load_from_bin(&mut self, content: &[u8]) -> Result<Rom, RomError> {
+        let rom = Rom::from_bytes(content)?;
+        self.load(&rom)?;
+        Ok(rom)
     }
 
-    pub fn load_from_bin(&mut self, content: &[u8]) -> Result<(), &str>{
-        if self.ram.len() - 0x200 < content.len() {
-            return Err("Ram from this little chip can not handle this.");
+    // Copies the rom to the profile's load address and points the pc at its start address.
+    // Anything left over from a previously loaded rom is cleared, but not a font placed
+    // past the load address, unless the rom itself covers it.
+    pub fn load(&mut self, rom: &Rom) -> Result<(), RomError> {
+        let addr = self.profile.load_addr;
+        let max = CHIP8_RAM.saturating_sub(addr);
+        if rom.size() > max {
+            return Err(RomError::TooLarge {
+                size: rom.size(),
+                max,
+            });
+        }
+        self.ram[addr..].fill(0);
+        let font_addr = self.profile.font_addr;
+        self.ram[font_addr..font_addr + self.profile.font.len()]
+            .copy_from_slice(&self.profile.font);
+        self.ram[addr..(addr + rom.size())].copy_from_slice(rom.bytes());
+        self.pc = self.profile.start_pc;
+        self.initial_ram = self.ram;
+        self.invalidate_decoded(0..CHIP8_RAM);
+        Ok(())
+    }
+
+    // Power cycle: ram goes back to the font and loaded rom, the screen is cleared and
+    // the cpu restarts. The loaded program, quirks, profile and tick rate are kept.
+    pub fn reset(&mut self) {
+        self.ram = self.initial_ram;
+        self.invalidate_decoded(0..CHIP8_RAM);
+        self.screen = [[0; CHIP8_SCREEN_WIDTH]; CHIP8_SCREEN_HEIGHT];
+        self.soft_reset();
+    }
+
+    // Restarts the cpu only: registers, stack and timers are cleared and the pc goes back
+    // to the start address, but ram (including anything the program wrote) and the screen are left.
+    pub fn soft_reset(&mut self) {
+        self.v = [0; 16];
+        self.i = 0;
+        self.pc = self.profile.start_pc;
+        self.sp = 0;
+        self.stack = [0; CHIP8_MAX_STACK_DEPTH];
+        self.delay_timer = 0;
+        self.sound_timer = 0;
+        self.pressed = [false; 16];
+        self.released = [false; 16];
+        self.key_wait = None;
+        self.drawn = false;
+        self.cycle_budget = 0;
+    }
+
+    // One frame with a single instruction, the keypad given as its full state.
+    pub fn cycle(&mut self, keypad: [bool; 16]) -> Result<(), Chip8Error> {
+        self.set_keypad(keypad);
+        self.tick_timers();
+        let result = self.exec();
+        self.end_frame();
+        result
+    }
+
+    // One 60 Hz frame: the timers tick once and `tick_rate` instructions run.
+    pub fn run_frame(&mut self, keypad: [bool; 16]) -> Result<(), Chip8Error> {
+        self.set_keypad(keypad);
+        self.frame()
+    }
+
+    // `run_frame` for frontends that report input through `key_down`/`key_up`.
+    // The frame stops at the first instruction that fails.
+    pub fn frame(&mut self) -> Result<(), Chip8Error> {
+        self.frame_with(|_| ())
+    }
+
+    // `frame`, calling `before` ahead of every instruction, for profilers and tracers.
+    pub fn frame_with(&mut self, mut before: impl FnMut(&Chip8)) -> Result<(), Chip8Error> {
+        self.tick_timers();
+        let result = match self.timing {
+            Timing::Instructions => (0..self.tick_rate.max(1)).try_for_each(|_| {
+                before(self);
+                self.exec()
+            }),
+            Timing::Vip => self.run_vip_cycles(&mut before),
+        };
+        self.end_frame();
+        result
+    }
+
+    // Runs instructions until the frame's machine cycles are used up. Dxyn waits for
+    // the display interrupt on the VIP, which gives up the rest of the frame.
+    fn run_vip_cycles(&mut self, before: &mut impl FnMut(&Chip8)) -> Result<(), Chip8Error> {
+        self.cycle_budget += timing::VIP_FRAME_CYCLES - timing::VIP_DISPLAY_CYCLES;
+        while self.cycle_budget > 0 {
+            before(self);
+            let cycles = timing::vip_cycles(self);
+            let draws = self.pc + 1 < CHIP8_RAM && self.get_opcode() & 0xF000 == 0xD000;
+            self.exec()?;
+            self.cycle_budget -= cycles as i64;
+            if draws {
+                self.cycle_budget = self.cycle_budget.min(0);
+                break;
+            }
         }
-        self.ram[0x200..(0x200 + content.len())].clone_from_slice(content);
         Ok(())
     }
 
-    pub fn cycle(&mut self, keypad: [bool; 16]) {
-        self.keypad = keypad;
+    pub fn tick_timers(&mut self) {
         if self.delay_timer > 0 {
             self.delay_timer -= 1
         }
         if self.sound_timer > 0 {
             self.sound_timer -= 1
         }
-        self.exec();
+        self.drawn = false;
     }
 
-    pub fn exec(&mut self) {
-        let opcode: u16 = self.get_opcode();
+    // Forgets which keys went down or up, so edges are only seen for the frame they happened in.
+    pub fn end_frame(&mut self) {
+        self.pressed = [false; 16];
+        self.released = [false; 16];
+    }
+
+    pub fn key_down(&mut self, key: usize) {
+        if !self.keypad[key & 0xF] {
+            self.keypad[key & 0xF] = true;
+            self.pressed[key & 0xF] = true;
+            if let Some(waiting) = self.key_wait.as_mut() {
+                waiting[key & 0xF] = true;
+            }
+        }
+    }
+
+    pub fn key_up(&mut self, key: usize) {
+        if self.keypad[key & 0xF] {
+            self.keypad[key & 0xF] = false;
+            self.released[key & 0xF] = true;
+        }
+    }
+
+    // Turns a full keypad state into key_down/key_up events for the keys that changed.
+    pub fn set_keypad(&mut self, keypad: [bool; 16]) {
+        for (key, down) in keypad.iter().enumerate() {
+            if *down {
+                self.key_down(key);
+            } else {
+                self.key_up(key);
+            }
+        }
+    }
+
+    // Makes Cxkk deterministic, for reproducible runs.
+    pub fn seed(&mut self, seed: u64) {
+        self.rng = seed_rng(seed);
+    }
+
+    pub(crate) fn rng_state(&self) -> u64 {
+        self.rng
+    }
+
+    // For states loaded while Fx0A waits, which start the wait over
+    pub(crate) fn cancel_key_wait(&mut self) {
+        self.key_wait = None;
+    }
+
+    pub(crate) fn set_rng_state(&mut self, rng: u64) {
+        self.rng = rng;
+    }
+
+    fn random_byte(&mut self) -> u8 {
+        self.rng ^= self.rng >> 12;
+        self.rng ^= self.rng << 25;
+        self.rng ^= self.rng >> 27;
+        (self.rng.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 56) as u8
+    }
+
+    pub fn is_key_down(&self, key: usize) -> bool {
+        self.keypad[key & 0xF]
+    }
+
+    // Went down since the last frame
+    pub fn is_key_pressed(&self, key: usize) -> bool {
+        self.pressed[key & 0xF]
+    }
+
+    // Went up since the last frame
+    pub fn is_key_released(&self, key: usize) -> bool {
+        self.released[key & 0xF]
+    }
+
+    // Levels of calls the profile's stack holds
+    pub fn stack_depth(&self) -> usize {
+        self.profile.stack.depth
+    }
+
+    // Return address in `slot` (0 is the outermost call), out of ram if the profile
+    // keeps the stack there
+    pub fn stack_slot(&self, slot: usize) -> usize {
+        match self.profile.stack.addr {
+            Some(addr) => {
+                let addr = addr + slot * 2;
+                ((self.ram[addr] as usize) << 8 | self.ram[addr + 1] as usize) & 0xFFF
+            }
+            None => self.stack[slot],
+        }
+    }
+
+    pub fn set_stack_slot(&mut self, slot: usize, value: usize) {
+        match self.profile.stack.addr {
+            Some(addr) => {
+                let addr = addr + slot * 2;
+                self.ram[addr] = (value >> 8) as u8;
+                self.ram[addr + 1] = value as u8;
+                self.invalidate_decoded(addr..addr + 2);
+            }
+            None => self.stack[slot] = value,
+        }
+    }
+
+    // Return addresses of the calls in progress, outermost first
+    pub fn call_stack(&self) -> Vec<usize> {
+        (0..self.sp.min(self.stack_depth()))
+            .map(|slot| self.stack_slot(slot))
+            .collect()
+    }
+
+    // Stuck on a jump to itself, which is how most programs end.
+    pub fn is_halted(&self) -> bool {
+        self.pc + 1 < CHIP8_RAM && self.get_opcode() == 0x1000 | self.pc as u16
+    }
+
+    pub fn exec(&mut self) -> Result<(), Chip8Error> {
+        let pc = self.pc;
+        self.exec_at(pc).inspect_err(|_| self.pc = pc)
+    }
+
+    fn exec_at(&mut self, pc: usize) -> Result<(), Chip8Error> {
+        if pc + 1 >= CHIP8_RAM {
+            return Err(Chip8Error::PcOutOfRange { pc });
+        }
+        let instruction = match self.decoded[pc] {
+            Some(instruction) if self.decode_cache => instruction,
+            _ => {
+                let instruction = Instruction::decode(self.get_opcode());
+                if self.decode_cache {
+                    self.decoded[pc] = Some(instruction);
+                }
+                instruction
+            }
+        };
         self.pc += 2;
-        let (i, x, y, n, kk, nnn) = Self::inst_decode(&opcode);
-        //println!("{:#x} {:#x} {:#x} {:#x} {:#x}", i, x, y, n, nnn);
-
-        match (i, x, y, n) {
-            (0x00, 0x00, 0x0e, 0x00) => self.inst_00e0(),
-            (0x00, 0x00, 0x0e, 0x0e) => self.inst_00ee(),
-            (0x01, _, _, _) => self.inst_1nnn(nnn),
-            (0x02, _, _, _) => self.inst_2nnn(nnn),
-            (0x03, _, _, _) => self.inst_3xkk(x, kk),
-            (0x04, _, _, _) => self.inst_4xkk(x, kk),
-            (0x05, _, _, 0x00) => self.inst_5xy0(x, y),
-            (0x06, _, _, _) => self.inst_6xkk(x, kk),
-            (0x07, _, _, _) => self.inst_7xkk(x, kk),
-            (0x08, _, _, 0x00) => self.inst_8xy0(x, y),
-            (0x08, _, _, 0x01) => self.inst_8xy1(x, y),
-            (0x08, _, _, 0x02) => self.inst_8xy2(x, y),
-            (0x08, _, _, 0x03) => self.inst_8xy3(x, y),
-            (0x08, _, _, 0x04) => self.inst_8xy4(x, y),
-            (0x08, _, _, 0x05) => self.inst_8xy5(x, y),
-            (0x08, _, _, 0x06) => self.inst_8x06(x),
-            (0x08, _, _, 0x07) => self.inst_8xy7(x, y),
-            (0x08, _, _, 0x0e) => self.inst_8xye(x),
-            (0x09, _, _, 0x00) => self.inst_9xy0(x, y),
-            (0x0a, _, _, _) => self.inst_annn(nnn),
-            (0x0b, _, _, _) => self.inst_bnnn(nnn),
-            (0x0c, _, _, _) => self.inst_cxkk(x, kk),
-            (0x0d, _, _, _) => self.inst_dxyn(x, y, n),
-            (0x0e, _, 0x09, 0x0e) => self.inst_ex9e(x),
-            (0x0e, _, 0x0a, 0x01) => self.inst_exa1(x),
-            (0x0f, _, 0x00, 0x07) => self.inst_fx07(x),
-            (0x0f, _, 0x00, 0x0a) => self.inst_fx0a(x),
-            (0x0f, _, 0x01, 0x05) => self.inst_fx15(x),
-            (0x0f, _, 0x01, 0x08) => self.inst_fx18(x),
-            (0x0f, _, 0x01, 0x0e) => self.inst_fx1e(x),
-            (0x0f, _, 0x02, 0x09) => self.inst_fx29(x),
-            (0x0f, _, 0x03, 0x03) => self.inst_fx33(x),
-            (0x0f, _, 0x05, 0x05) => self.inst_fx55(x),
-            (0x0f, _, 0x06, 0x05) => self.inst_fx65(x),
-            _ => (),
+
+        match instruction {
+            Instruction::Cls => self.inst_00e0(),
+            Instruction::Ret => self.inst_00ee()?,
+            // 0nnn - SYS addr, a machine code routine on the original hardware; ignored
+            Instruction::Sys => (),
+            Instruction::Jp { nnn } => self.inst_1nnn(nnn as usize),
+            Instruction::Call { nnn } => self.inst_2nnn(nnn as usize)?,
+            Instruction::SeByte { x, kk } => self.inst_3xkk(x, kk),
+            Instruction::SneByte { x, kk } => self.inst_4xkk(x, kk),
+            Instruction::SeReg { x, y } => self.inst_5xy0(x, y),
+            Instruction::LdByte { x, kk } => self.inst_6xkk(x, kk),
+            Instruction::AddByte { x, kk } => self.inst_7xkk(x, kk),
+            Instruction::LdReg { x, y } => self.inst_8xy0(x, y),
+            Instruction::Or { x, y } => self.inst_8xy1(x, y),
+            Instruction::And { x, y } => self.inst_8xy2(x, y),
+            Instruction::Xor { x, y } => self.inst_8xy3(x, y),
+            Instruction::AddReg { x, y } => self.inst_8xy4(x, y),
+            Instruction::Sub { x, y } => self.inst_8xy5(x, y),
+            Instruction::Shr { x, y } => self.inst_8xy6(x, y),
+            Instruction::Subn { x, y } => self.inst_8xy7(x, y),
+            Instruction::Shl { x, y } => self.inst_8xye(x, y),
+            Instruction::SneReg { x, y } => self.inst_9xy0(x, y),
+            Instruction::LdI { nnn } => self.inst_annn(nnn as usize),
+            Instruction::JpV0 { x, nnn } => self.inst_bnnn(x, nnn as usize),
+            Instruction::Rnd { x, kk } => self.inst_cxkk(x, kk),
+            Instruction::Drw { x, y, n } => self.inst_dxyn(x, y, n)?,
+            Instruction::Skp { x } => self.inst_ex9e(x),
+            Instruction::Sknp { x } => self.inst_exa1(x),
+            Instruction::LdVxDt { x } => self.inst_fx07(x),
+            Instruction::LdVxK { x } => self.inst_fx0a(x),
+            Instruction::LdDtVx { x } => self.inst_fx15(x),
+            Instruction::LdStVx { x } => self.inst_fx18(x),
+            Instruction::AddI { x } => self.inst_fx1e(x),
+            Instruction::LdF { x } => self.inst_fx29(x),
+            Instruction::LdB { x } => self.inst_fx33(x)?,
+            Instruction::Store { x } => self.inst_fx55(x)?,
+            Instruction::Load { x } => self.inst_fx65(x)?,
+            Instruction::Invalid { opcode } => {
+                return Err(Chip8Error::InvalidOpcode { pc, opcode })
+            }
+        };
+        Ok(())
+    }
+
+    pub fn ram(&self) -> &[u8; CHIP8_RAM] {
+        &self.ram
+    }
+
+    // Copies `bytes` into ram from `addr`, for debuggers and the like. Panics if they
+    // don't fit.
+    pub fn write_ram(&mut self, addr: usize, bytes: &[u8]) {
+        self.ram[addr..addr + bytes.len()].copy_from_slice(bytes);
+        self.invalidate_decoded(addr..addr + bytes.len());
+    }
+
+    // Ram written since the last call, by the program or anything else, for code that
+    // keeps what it found there, like the JIT. One range covering every write; a new
+    // machine starts out with all of ram written.
+    pub fn take_written(&mut self) -> Option<Range<usize>> {
+        self.written.take().map(|(start, end)| start..end)
+    }
+
+    // Forgets the decoded instructions overlapping `range`, after it was written
+    fn invalidate_decoded(&mut self, range: Range<usize>) {
+        let end = range.end.min(CHIP8_RAM);
+        let start = range.start.min(end);
+        self.written = match self.written {
+            Some((from, to)) => Some((from.min(start), to.max(end))),
+            None => Some((start, end)),
         };
+        let start = start.saturating_sub(1);
+        self.decoded[start..end].fill(None);
+    }
+
+    // Fails unless `len` bytes from I are in ram. Called after the pc moved past the instruction.
+    fn check_memory(&self, len: usize) -> Result<(), Chip8Error> {
+        if self.i + len > CHIP8_RAM {
+            return Err(Chip8Error::MemoryOutOfRange {
+                pc: self.pc - 2,
+                i: self.i,
+            });
+        }
+        Ok(())
     }
 
     // Instruction (ie. 0x0000)
@@ -170,9 +564,13 @@ impl Chip8 {
     // 00ee - RET
     // Return from a subroutine.
     // The interpreter sets the program counter to the address at the top of the stack, then subtracts 1 from the stack point
-    fn inst_00ee(&mut self) {
+    fn inst_00ee(&mut self) -> Result<(), Chip8Error> {
+        if self.sp == 0 {
+            return Err(Chip8Error::StackUnderflow { pc: self.pc - 2 });
+        }
         self.sp -= 1;
-        self.pc = self.stack[self.sp];
+        self.pc = self.stack_slot(self.sp);
+        Ok(())
     }
 
     // 1nnn - JP addr
@@ -185,10 +583,14 @@ impl Chip8 {
     // 2nnn - CALL addr
     // Call subroutine at nnn.
     // The interpreter increments the stack pointer, then puts the current PC on the top of the stack. The PC is then set to nnn.
-    fn inst_2nnn(&mut self, nnn: usize) {
-        self.stack[self.sp] = self.pc;
+    fn inst_2nnn(&mut self, nnn: usize) -> Result<(), Chip8Error> {
+        if self.sp >= self.stack_depth() {
+            return Err(Chip8Error::StackOverflow { pc: self.pc - 2 });
+        }
+        self.set_stack_slot(self.sp, self.pc);
         self.sp += 1;
         self.pc = nnn;
+        Ok(())
     }
 
     // 3xkk - SE Vx, byte
@@ -245,6 +647,9 @@ impl Chip8 {
     // and if the bits are not both the same, then the corresponding bit in the result is set to 1. Otherwise, it is 0.
     fn inst_8xy1(&mut self, x: u8, y: u8) {
         self.v[x as usize] |= self.v[y as usize];
+        if self.quirks.vf_reset {
+            self.v[0xF] = 0;
+        }
     }
 
     // 8xy2 - AND Vx, Vy
@@ -254,6 +659,9 @@ impl Chip8 {
     // and if both bits are 1, then the same bit in the result is also 1. Otherwise, it is 0.
     fn inst_8xy2(&mut self, x: u8, y: u8) {
         self.v[x as usize] &= self.v[y as usize];
+        if self.quirks.vf_reset {
+            self.v[0xF] = 0;
+        }
     }
 
     // 8xy3 - XOR Vx, Vy
@@ -263,6 +671,9 @@ impl Chip8 {
     // and if the bits are not both the same, then the corresponding bit in the result is set to 1. Otherwise, it is 0.
     fn inst_8xy3(&mut self, x: u8, y: u8) {
         self.v[x as usize] ^= self.v[y as usize];
+        if self.quirks.vf_reset {
+            self.v[0xF] = 0;
+        }
     }
 
     // 8xy4 - ADD Vx, Vy
@@ -290,9 +701,12 @@ impl Chip8 {
     // 8xy6 - SHR Vx {, Vy}
     // Set Vx = Vx SHR 1.
     // If the least-significant bit of Vx is 1, then VF is set to 1, otherwise 0. Then Vx is divided by 2.
-    fn inst_8x06(&mut self, x: u8) {
-        self.v[0xF] = self.v[x as usize] & 1;
-        self.v[x as usize] >>= 1;
+    // Without the shifting quirk Vy is shifted and the result stored in Vx.
+    fn inst_8xy6(&mut self, x: u8, y: u8) {
+        let src = if self.quirks.shifting { x } else { y };
+        let value = self.v[src as usize];
+        self.v[x as usize] = value >> 1;
+        self.v[0xF] = value & 1;
     }
 
     // 8xy7 - SUBN Vx, Vy
@@ -310,9 +724,12 @@ impl Chip8 {
     // 8xyE - SHL Vx {, Vy}
     // Set Vx = Vx SHL 1.
     // If the most-significant bit of Vx is 1, then VF is set to 1, otherwise to 0. Then Vx is multiplied by 2.
-    fn inst_8xye(&mut self, x: u8) {
-        self.v[0xF] = (self.v[x as usize] & 0x80) >> 7;
-        self.v[x as usize] <<= 1;
+    // Without the shifting quirk Vy is shifted and the result stored in Vx.
+    fn inst_8xye(&mut self, x: u8, y: u8) {
+        let src = if self.quirks.shifting { x } else { y };
+        let value = self.v[src as usize];
+        self.v[x as usize] = value << 1;
+        self.v[0xF] = (value & 0x80) >> 7;
     }
 
     // 9xy0 - SNE Vx, Vy
@@ -334,8 +751,10 @@ impl Chip8 {
     // Bnnn - JP V0, addr
     // Jump to location nnn + V0.
     // The program counter is set to nnn plus the value of V0.
-    fn inst_bnnn(&mut self, nnn: usize) {
-        self.pc = (self.v[0] as usize) + nnn
+    // With the jumping quirk this is Bxnn, and Vx is added instead of V0.
+    fn inst_bnnn(&mut self, x: u8, nnn: usize) {
+        let offset = if self.quirks.jumping { x } else { 0 };
+        self.pc = (self.v[offset as usize] as usize) + nnn
     }
 
     // Cxkk - RND Vx, byte
@@ -343,7 +762,7 @@ impl Chip8 {
     // The interpreter generates a random number from 0 to 255, which is then ANDed with the value kk.
     // The results are stored in Vx. See instruction 8xy2 for more information on AND.
     fn inst_cxkk(&mut self, x: u8, kk: u8) {
-        self.v[x as usize] = rand::thread_rng().gen::<u8>() & kk;
+        self.v[x as usize] = self.random_byte() & kk;
     }
 
     // Dxyn - DRW Vx, Vy, nibble
@@ -353,24 +772,43 @@ impl Chip8 {
     // If this causes any pixels to be erased, VF is set to 1, otherwise it is set to 0.
     // If the sprite is positioned so part of it is outside the coordinates of the display, it wraps around to the opposite side of the screen.
     // See instruction 8xy3 for more information on XOR, and section 2.4, Display, for more information on the Chip-8 screen and sprites.
-    fn inst_dxyn(&mut self, x: u8, y: u8, n: u8) {
+    // With the clipping quirk only the starting position wraps, and the rest of the sprite is cut off at the edges.
+    // With the display wait quirk the instruction is retried until the next frame if a sprite was already drawn in this one.
+    fn inst_dxyn(&mut self, x: u8, y: u8, n: u8) -> Result<(), Chip8Error> {
+        self.check_memory(n as usize)?;
+        if self.quirks.display_wait {
+            if self.drawn {
+                self.pc -= 2;
+                return Ok(());
+            }
+            self.drawn = true;
+        }
         self.v[0xF] = 0;
+        let x0 = self.v[x as usize] as usize % CHIP8_SCREEN_WIDTH;
+        let y0 = self.v[y as usize] as usize % CHIP8_SCREEN_HEIGHT;
         for byte in 0..n as usize {
-            let y = (self.v[y as usize] as usize + byte) % CHIP8_SCREEN_HEIGHT;
+            if self.quirks.clipping && y0 + byte >= CHIP8_SCREEN_HEIGHT {
+                break;
+            }
+            let y = (y0 + byte) % CHIP8_SCREEN_HEIGHT;
             for bit in 0..8 {
-                let x = (self.v[x as usize] as usize + bit) % CHIP8_SCREEN_WIDTH;
+                if self.quirks.clipping && x0 + bit >= CHIP8_SCREEN_WIDTH {
+                    break;
+                }
+                let x = (x0 + bit) % CHIP8_SCREEN_WIDTH;
                 let color = (self.ram[self.i + byte] >> (7 - bit)) & 1;
                 self.v[0xF] |= color & self.screen[y][x];
                 self.screen[y][x] ^= color;
             }
         }
+        Ok(())
     }
 
     // Ex9E - SKP Vx
     // Skip next instruction if key with the value of Vx is pressed.
     // Checks the keyboard, and if the key corresponding to the value of Vx is currently in the down position, PC is increased by 2.
     fn inst_ex9e(&mut self, x: u8) {
-        if self.keypad[self.v[x as usize] as usize] {
+        if self.keypad[(self.v[x as usize] & 0xF) as usize] {
             self.pc += 2
         }
     }
@@ -379,7 +817,7 @@ impl Chip8 {
     // Skip next instruction if key with the value of Vx is not pressed.
     // Checks the keyboard, and if the key corresponding to the value of Vx is currently in the up position, PC is increased by 2.
     fn inst_exa1(&mut self, x: u8) {
-        if !self.keypad[self.v[x as usize] as usize] {
+        if !self.keypad[(self.v[x as usize] & 0xF) as usize] {
             self.pc += 2;
         }
     }
@@ -394,13 +832,17 @@ impl Chip8 {
     // Fx0A - LD Vx, K
     // Wait for a key press, store the value of the key in Vx.
     // All execution stops until a key is pressed, then the value of that key is stored in Vx.
+    // Like on the COSMAC VIP, the instruction completes once the key is released again.
+    // Only a key pressed while waiting counts, so one still held from before doesn't
+    // end the wait as soon as it's let go.
     fn inst_fx0a(&mut self, x: u8) {
-        for i in 0..self.keypad.len() {
-            if self.keypad[i] {
-                self.keypad[i] = false;
-                self.v[x as usize] = i as u8;
-                return;
-            }
+        let waiting = self.key_wait.get_or_insert([false; 16]);
+        let released = (0..16).find(|&key| waiting[key] && !self.keypad[key]);
+        if let Some(key) = released {
+            self.key_wait = None;
+            self.released[key] = false;
+            self.v[x as usize] = key as u8;
+            return;
         }
         self.pc -= 2;
     }
@@ -422,43 +864,74 @@ impl Chip8 {
     // Fx1E - ADD I, Vx
     // Set I = I + Vx.
     // The values of I and Vx are added, and the results are stored in I.
+    // I is 16 bits wide, so it can point past the end of ram (reading or writing there is
+    // an error) and wraps around at 0xFFFF.
     fn inst_fx1e(&mut self, x: u8) {
-        self.i += self.v[x as usize] as usize;
+        self.i = (self.i + self.v[x as usize] as usize) & 0xFFFF;
     }
 
     // Fx29 - LD F, Vx
     // Set I = location of sprite for digit Vx.
     // The value of I is set to the location for the hexadecimal sprite corresponding to the value of Vx.
     // See section 2.4, Display, for more information on the Chip-8 hexadecimal font.
+    // Only the low nibble of Vx is used, and the font is wherever the profile placed it.
     fn inst_fx29(&mut self, x: u8) {
-        self.i = (self.v[x as usize] as usize) * 5 + 0x50;
+        self.i = ((self.v[x as usize] & 0x0F) as usize) * 5 + self.profile.font_addr;
     }
 
     // Fx33 - LD B, Vx
     // Store BCD representation of Vx in memory locations I, I+1, and I+2.
     // The interpreter takes the decimal value of Vx, and places the hundreds digit in memory at location in I,
     // the tens digit at location I+1, and the ones digit at location I+2.
-    fn inst_fx33(&mut self, x: u8) {
+    fn inst_fx33(&mut self, x: u8) -> Result<(), Chip8Error> {
+        self.check_memory(3)?;
         self.ram[self.i] = self.v[x as usize] / 100;
         self.ram[self.i + 1] = self.v[x as usize] % 100 / 10;
         self.ram[self.i + 2] = self.v[x as usize] % 10;
+        self.invalidate_decoded(self.i..self.i + 3);
+        Ok(())
     }
 
     // Fx55 - LD [I], Vx
     // Store registers V0 through Vx in memory starting at location I.
     // The interpreter copies the values of registers V0 through Vx into memory, starting at the address in I.
-    fn inst_fx55(&mut self, x: u8) {
+    fn inst_fx55(&mut self, x: u8) -> Result<(), Chip8Error> {
+        self.check_memory(x as usize + 1)?;
         for i in 0..=x as usize {
             self.ram[self.i + i] = self.v[i];
         }
+        self.invalidate_decoded(self.i..self.i + x as usize + 1);
+        if self.quirks.memory {
+            self.i += x as usize + 1;
+        }
+        Ok(())
     }
 
     // Fx65 - LD Vx, [I]
     // Read registers V0 through Vx from memory starting at location I.
     // The interpreter reads values from memory starting at location I into registers V0 through Vx.
-    fn inst_fx65(&mut self, x: u8) {
+    fn inst_fx65(&mut self, x: u8) -> Result<(), Chip8Error> {
+        self.check_memory(x as usize + 1)?;
         for i in 0..=x as usize {
             self.v[i] = self.ram[self.i + i];
         }
+        if self.quirks.memory {
+            self.i += x as usize + 1;
+        }
+        Ok(())
+    }
+}
+
+// Spreads the seed over the state (splitmix64) so that small seeds still give good
+// sequences, and keeps it away from 0, which xorshift can't leave.
+fn seed_rng(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    if z == 0 {
+        1
+    } else {
+        z
     }
 }
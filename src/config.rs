@@ -0,0 +1,178 @@
+use crate::chip8::{Chip8, CHIP8_FONTSET, CHIP8_RAM};
+use crate::font::FontSet;
+use crate::keymap::{KeyMap, KeyMapError};
+use crate::palette::Palette;
+use crate::profile::{Profile, StackLayout};
+use crate::quirks::Quirks;
+use crate::rom::{Rom, RomError};
+use crate::romdb::{RomDatabase, RomDatabaseError, RomInfo};
+use crate::timing::Timing;
+use std::path::PathBuf;
+
+// Instructions per second when neither the settings nor the rom database say otherwise
+pub const DEFAULT_HZ: usize = 600;
+
+// How the frontends set up a machine for a rom, so they all do it the same way. Settings
+// left out come from the rom database, then from the defaults. With the `clap` feature
+// these are also the command line options the binaries share.
+
+// Memory layout: where things go in ram
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "clap", derive(clap::Args))]
+pub struct ProfileConfig {
+    /// Memory layout of the machine the rom was written for: chip8, vip (font at 0,
+    /// stack in ram) or eti660 (loaded at 0x600) [default: chip8]
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub profile: Option<Profile>,
+    /// Where the rom is loaded, in hex, e.g. "600" [default: from --profile]
+    #[cfg_attr(feature = "clap", arg(long, value_parser = parse_addr))]
+    pub load_addr: Option<usize>,
+    /// Where execution starts, in hex [default: the load address]
+    #[cfg_attr(feature = "clap", arg(long, value_parser = parse_addr))]
+    pub start_addr: Option<usize>,
+    /// Font: octo, vip, dream6800, eti660
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub font: Option<FontSet>,
+    /// Where the font goes, in hex [default: from --profile, 0x50 for chip8]
+    #[cfg_attr(feature = "clap", arg(long, value_parser = parse_font_addr))]
+    pub font_addr: Option<usize>,
+    /// Call stack depth, and "@<hex address>" to keep it in ram like the VIP did,
+    /// e.g. "12@ea0" [default: 16, outside ram]
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub stack: Option<StackLayout>,
+}
+
+impl ProfileConfig {
+    pub fn profile(&self) -> Profile {
+        let mut profile = self.profile.unwrap_or_default();
+        if self.load_addr.is_some() || self.start_addr.is_some() {
+            let load_addr = self.load_addr.unwrap_or(profile.load_addr);
+            profile = profile.with_load_addr(load_addr, self.start_addr.unwrap_or(load_addr));
+        }
+        let font_addr = self.font_addr.unwrap_or(profile.font_addr);
+        profile = match self.font {
+            Some(font) => profile.with_font(font, font_addr),
+            None => Profile {
+                font_addr,
+                ..profile
+            },
+        };
+        if let Some(stack) = self.stack {
+            profile = profile.with_stack(stack);
+        }
+        profile
+    }
+}
+
+// A ram address in hex, "600" or "0x600"
+pub fn parse_addr(s: &str) -> Result<usize, String> {
+    let digits = s.trim().trim_start_matches("0x");
+    let addr = usize::from_str_radix(digits, 16).map_err(|_| format!("bad address '{}'", s))?;
+    if addr >= CHIP8_RAM {
+        return Err(format!("{:#05x} is past the end of ram", addr));
+    }
+    Ok(addr)
+}
+
+// A font address in hex, with room for the whole font after it
+pub fn parse_font_addr(s: &str) -> Result<usize, String> {
+    let addr = parse_addr(s)?;
+    if addr + CHIP8_FONTSET.len() > CHIP8_RAM {
+        return Err(format!("a font at {:#05x} doesn't fit in ram", addr));
+    }
+    Ok(addr)
+}
+
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "clap", derive(clap::Args))]
+pub struct MachineConfig {
+    #[cfg_attr(feature = "clap", command(flatten))]
+    pub profile: ProfileConfig,
+    /// Instructions per second [default: from the rom database, or 600]
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub hz: Option<usize>,
+    /// Quirks preset (default, chip8, superchip, xochip) with optional changes,
+    /// e.g. "superchip,-clipping" [default: from the rom database]
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub quirks: Option<Quirks>,
+    /// Instructions per frame as set by --hz ("instructions"), or "vip" to run as many
+    /// as the COSMAC VIP had machine cycles for, each taking as long as it did there
+    #[cfg_attr(feature = "clap", arg(long, default_value_t = Timing::Instructions))]
+    pub timing: Timing,
+    /// Seed the random number generator, for reproducible runs
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub seed: Option<u64>,
+    /// Rom database to look the rom up in before the bundled one, a programs.json
+    /// from https://github.com/chip-8/chip-8-database
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub romdb: Option<PathBuf>,
+}
+
+impl MachineConfig {
+    // What `romdb`, or else the bundled database, knows about `rom`
+    pub fn rom_info(&self, rom: &Rom) -> Result<Option<RomInfo>, RomDatabaseError> {
+        if let Some(path) = self.romdb.as_ref() {
+            if let Some(info) = RomDatabase::from_path(path)?.lookup(rom) {
+                return Ok(Some(info.clone()));
+            }
+        }
+        Ok(RomDatabase::bundled().lookup(rom).cloned())
+    }
+
+    // A machine with `rom` loaded, set up by these settings over what `info` says
+    pub fn chip8(&self, rom: &Rom, info: Option<&RomInfo>) -> Result<Chip8, RomError> {
+        let mut chip8 = Chip8::with_profile(self.profile.profile());
+        chip8.load(rom)?;
+        chip8.tick_rate = DEFAULT_HZ / 60;
+        if let Some(info) = info {
+            chip8.apply_rom_info(info);
+        }
+        if let Some(quirks) = self.quirks {
+            chip8.quirks = quirks;
+        }
+        chip8.timing = self.timing;
+        if let Some(hz) = self.hz {
+            chip8.tick_rate = (hz / 60).max(1);
+        }
+        if let Some(seed) = self.seed {
+            chip8.seed(seed);
+        }
+        Ok(chip8)
+    }
+}
+
+// What only frontends with a screen and keys need
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "clap", derive(clap::Args))]
+pub struct FrontendConfig {
+    /// Preset (mono, octo, amber, green, lcd) or "<background>,<foreground>" in hex
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub palette: Option<Palette>,
+    /// Key bindings, TOML or (by extension) JSON
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub keymap: Option<PathBuf>,
+}
+
+impl FrontendConfig {
+    pub fn palette(&self, info: Option<&RomInfo>) -> Palette {
+        self.palette
+            .or_else(|| {
+                info.and_then(|i| i.colors.as_ref())
+                    .and_then(Palette::from_rom_colors)
+            })
+            .unwrap_or_default()
+    }
+
+    // The bindings for `rom`: the file's, or the default ones, with the rom database's
+    // controls and the file's overrides for the rom on top
+    pub fn keymap(&self, rom: &Rom, info: Option<&RomInfo>) -> Result<KeyMap, KeyMapError> {
+        let mut keymap = match self.keymap.as_ref() {
+            Some(path) => KeyMap::from_path(path)?,
+            None => KeyMap::default(),
+        };
+        if let Some(info) = info {
+            keymap = keymap.with_rom_controls(&info.keys);
+        }
+        Ok(keymap.for_rom(rom))
+    }
+}
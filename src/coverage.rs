@@ -0,0 +1,199 @@
+use crate::chip8::{Chip8, Chip8Error, CHIP8_RAM};
+use crate::disasm;
+use crate::instruction::Instruction;
+use std::fmt;
+use std::io::{self, Write};
+use std::ops::Range;
+
+// Flags per address in the bitmap
+pub const EXECUTED: u8 = 1;
+pub const READ: u8 = 2;
+pub const WRITTEN: u8 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoverageError {
+    WrongSize { size: usize },
+}
+
+impl fmt::Display for CoverageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoverageError::WrongSize { size } => write!(
+                f,
+                "coverage bitmap is {} bytes, expected one per ram address ({})",
+                size, CHIP8_RAM
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CoverageError {}
+
+// Which ram addresses a run executed as instructions, read as data (Dxyn, Fx65, 00EE
+// with the stack in ram) and wrote (Fx33, Fx55, 2nnn with the stack in ram). Runs
+// with different input cover different paths; `merge` adds them up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coverage {
+    // EXECUTED | READ | WRITTEN per address
+    pub map: Vec<u8>,
+}
+
+impl Default for Coverage {
+    fn default() -> Self {
+        Self {
+            map: vec![0; CHIP8_RAM],
+        }
+    }
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Coverage written by `to_bitmap`
+    pub fn from_bitmap(bitmap: &[u8]) -> Result<Self, CoverageError> {
+        if bitmap.len() != CHIP8_RAM {
+            return Err(CoverageError::WrongSize { size: bitmap.len() });
+        }
+        Ok(Self {
+            map: bitmap.to_vec(),
+        })
+    }
+
+    // One byte of flags per address, 4096 bytes
+    pub fn to_bitmap(&self) -> &[u8] {
+        &self.map
+    }
+
+    // Adds what `other` covered
+    pub fn merge(&mut self, other: &Coverage) {
+        self.map
+            .iter_mut()
+            .zip(other.map.iter())
+            .for_each(|(a, b)| *a |= b);
+    }
+
+    pub fn is(&self, addr: usize, flag: u8) -> bool {
+        self.map.get(addr).is_some_and(|flags| flags & flag != 0)
+    }
+
+    // Addresses in `range` with `flag`
+    pub fn count(&self, range: Range<usize>, flag: u8) -> usize {
+        range.filter(|addr| self.is(*addr, flag)).count()
+    }
+
+    // Runs one frame of `chip8` while recording.
+    pub fn frame(&mut self, chip8: &mut Chip8) -> Result<(), Chip8Error> {
+        chip8.frame_with(|chip8| self.observe(chip8))
+    }
+
+    // Records the instruction `chip8` is about to run, for callers running it themselves.
+    pub fn observe(&mut self, chip8: &Chip8) {
+        if chip8.pc + 1 >= CHIP8_RAM {
+            return;
+        }
+        self.mark(chip8.pc..chip8.pc + 2, EXECUTED);
+        if let Some((range, flag)) = data_access(chip8) {
+            self.mark(range, flag);
+        }
+    }
+
+    fn mark(&mut self, range: Range<usize>, flag: u8) {
+        self.map[range].iter_mut().for_each(|flags| *flags |= flag);
+    }
+
+    // Disassembly of `code`, loaded at `base`, with what was done to each line:
+    //
+    //     0x200  6001  x--  LD V0, 0x01
+    //     0x202  1208  ---  JP 0x208
+    //     0x204  f0    -r-  DB 0xf0
+    //
+    // Bytes read or written but never executed are listed one by one as data.
+    pub fn write_listing<W: Write>(&self, mut w: W, code: &[u8], base: usize) -> io::Result<()> {
+        let range = base..base + code.len();
+        let size = code.len().max(1);
+        writeln!(
+            w,
+            "executed {} of {} bytes ({:.1}%), read {}, written {}",
+            self.count(range.clone(), EXECUTED),
+            code.len(),
+            self.count(range.clone(), EXECUTED) as f64 * 100.0 / size as f64,
+            self.count(range.clone(), READ),
+            self.count(range.clone(), WRITTEN),
+        )?;
+        let mut offset = 0;
+        while offset < code.len() {
+            let addr = base + offset;
+            let data = !self.is(addr, EXECUTED) && self.is(addr, READ | WRITTEN);
+            let single = offset + 1 == code.len()
+                || data
+                || (!self.is(addr, EXECUTED) && self.is(addr + 1, EXECUTED));
+            if single {
+                let byte = code[offset];
+                writeln!(
+                    w,
+                    "{:#05x}  {:02x}    {}  DB {:#04x}",
+                    addr,
+                    byte,
+                    self.flags(addr..addr + 1),
+                    byte
+                )?;
+                offset += 1;
+            } else {
+                let opcode = (code[offset] as u16) << 8 | code[offset + 1] as u16;
+                writeln!(
+                    w,
+                    "{:#05x}  {:04x}  {}  {}",
+                    addr,
+                    opcode,
+                    self.flags(addr..addr + 2),
+                    disasm::mnemonic(opcode)
+                )?;
+                offset += 2;
+            }
+        }
+        Ok(())
+    }
+
+    fn flags(&self, range: Range<usize>) -> String {
+        [(EXECUTED, 'x'), (READ, 'r'), (WRITTEN, 'w')]
+            .iter()
+            .map(|(flag, c)| {
+                if range.clone().any(|addr| self.is(addr, *flag)) {
+                    *c
+                } else {
+                    '-'
+                }
+            })
+            .collect()
+    }
+}
+
+// The ram the instruction `chip8` is about to run reads (READ) or writes (WRITTEN),
+// cut off at the end of ram, past which the instruction fails anyway. With the stack
+// in ram, 2nnn writes the return address to its slot and 00EE reads it back.
+pub fn data_access(chip8: &Chip8) -> Option<(Range<usize>, u8)> {
+    if chip8.pc + 1 >= CHIP8_RAM {
+        return None;
+    }
+    let stack_slot = |slot: usize| {
+        let addr = chip8.profile.stack.addr? + slot * 2;
+        Some(addr..addr + 2)
+    };
+    let (len, flag) = match Instruction::decode(chip8.get_opcode()) {
+        Instruction::Call { .. } if chip8.sp < chip8.stack_depth() => {
+            return stack_slot(chip8.sp).map(|range| (range, WRITTEN))
+        }
+        Instruction::Ret if chip8.sp > 0 => {
+            return stack_slot(chip8.sp - 1).map(|range| (range, READ))
+        }
+        Instruction::Drw { n, .. } => (n as usize, READ),
+        Instruction::LdB { .. } => (3, WRITTEN),
+        Instruction::Store { x } => (x as usize + 1, WRITTEN),
+        Instruction::Load { x } => (x as usize + 1, READ),
+        _ => return None,
+    };
+    let end = (chip8.i + len).min(CHIP8_RAM);
+    Some((chip8.i.min(end)..end, flag))
+}
@@ -0,0 +1,243 @@
+use crate::chip8::Chip8;
+use crate::machine::{Framebuffer, Machine};
+use std::collections::VecDeque;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// One 60 Hz frame
+pub const FRAME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+// How far `advance` catches up after a stall before dropping time, 6 frames
+const MAX_BACKLOG: Duration = Duration::from_nanos(6_000_000_000 / 60);
+
+// Where a frontend shows the screen.
+pub trait DisplaySink {
+    // Called once per frame, also while paused
+    fn present(&mut self, screen: &Framebuffer, paused: bool);
+}
+
+// Where a frontend plays the buzzer.
+pub trait AudioSink {
+    // Called once per frame with whether the buzzer should sound
+    fn set_buzzer(&mut self, on: bool);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputEvent {
+    // CHIP-8 keys 0x0 - 0xF
+    KeyDown(usize),
+    KeyUp(usize),
+    // Pauses, or resumes when paused
+    Pause,
+    Reset,
+    // A fifth more or fewer instructions per frame
+    Faster,
+    Slower,
+    Quit,
+}
+
+// Where a frontend gets its input from.
+pub trait InputSource {
+    // What happened since the last call, polled once per frame
+    fn poll(&mut self) -> Vec<InputEvent>;
+}
+
+// Shows nothing
+pub struct NullDisplay;
+
+impl DisplaySink for NullDisplay {
+    fn present(&mut self, _screen: &Framebuffer, _paused: bool) {}
+}
+
+// Plays nothing
+pub struct NullAudio;
+
+impl AudioSink for NullAudio {
+    fn set_buzzer(&mut self, _on: bool) {}
+}
+
+// Never presses anything
+pub struct NullInput;
+
+impl InputSource for NullInput {
+    fn poll(&mut self) -> Vec<InputEvent> {
+        Vec::new()
+    }
+}
+
+// Keeps the last frame presented, for checking what a run shows.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryDisplay {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+    pub paused: bool,
+    // Frames presented so far
+    pub frames: usize,
+}
+
+impl DisplaySink for MemoryDisplay {
+    fn present(&mut self, screen: &Framebuffer, paused: bool) {
+        self.width = screen.width;
+        self.height = screen.height;
+        self.pixels.clear();
+        self.pixels.extend_from_slice(screen.pixels);
+        self.paused = paused;
+        self.frames += 1;
+    }
+}
+
+// The buzzer state of every frame.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryAudio {
+    pub buzzer: Vec<bool>,
+}
+
+impl AudioSink for MemoryAudio {
+    fn set_buzzer(&mut self, on: bool) {
+        self.buzzer.push(on);
+    }
+}
+
+// Plays back input one frame's worth at a time, then asks to quit.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptedInput {
+    frames: VecDeque<Vec<InputEvent>>,
+}
+
+impl ScriptedInput {
+    pub fn new(frames: impl IntoIterator<Item = Vec<InputEvent>>) -> Self {
+        Self {
+            frames: frames.into_iter().collect(),
+        }
+    }
+}
+
+impl InputSource for ScriptedInput {
+    fn poll(&mut self) -> Vec<InputEvent> {
+        self.frames
+            .pop_front()
+            .unwrap_or_else(|| vec![InputEvent::Quit])
+    }
+}
+
+// A machine hooked up to a frontend's display, audio and input. Each frame polls the
+// input, runs the machine unless paused, and hands the screen and buzzer on, so a
+// frontend only has to implement the three traits.
+pub struct Emulator<D, A, I, M: Machine = Chip8> {
+    pub machine: M,
+    pub display: D,
+    pub audio: A,
+    pub input: I,
+    pub paused: bool,
+    // Set once the input asked to quit, no more frames run after that
+    pub quit: bool,
+    // The error that paused `run`, until taken
+    pub error: Option<M::Error>,
+    // Time given to `advance` that didn't make up a whole frame yet
+    pending: Duration,
+}
+
+impl<D, A, I, M> Emulator<D, A, I, M>
+where
+    D: DisplaySink,
+    A: AudioSink,
+    I: InputSource,
+    M: Machine,
+{
+    pub fn new(machine: M, display: D, audio: A, input: I) -> Self {
+        Self {
+            machine,
+            display,
+            audio,
+            input,
+            paused: false,
+            quit: false,
+            error: None,
+            pending: Duration::ZERO,
+        }
+    }
+
+    pub fn handle(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::KeyDown(key) => self.machine.key_down(key),
+            InputEvent::KeyUp(key) => self.machine.key_up(key),
+            InputEvent::Pause => self.paused = !self.paused,
+            // Starts over from an error too
+            InputEvent::Reset => {
+                self.machine.reset();
+                self.paused = false;
+                self.error = None;
+            }
+            InputEvent::Faster | InputEvent::Slower => {
+                let speed = self.machine.speed();
+                let step = (speed / 5).max(1);
+                self.machine.set_speed(if event == InputEvent::Faster {
+                    speed + step
+                } else {
+                    speed.saturating_sub(step).max(1)
+                });
+            }
+            InputEvent::Quit => self.quit = true,
+        }
+    }
+
+    // One 60 Hz frame. A machine error is returned with the machine left where it
+    // failed; the screen and buzzer are handed on either way.
+    pub fn frame(&mut self) -> Result<(), M::Error> {
+        for event in self.input.poll() {
+            self.handle(event);
+        }
+        if self.quit {
+            return Ok(());
+        }
+        let result = if self.paused {
+            Ok(())
+        } else {
+            self.machine.frame()
+        };
+        self.display
+            .present(&self.machine.framebuffer(), self.paused);
+        self.audio.set_buzzer(self.machine.sound() && !self.paused);
+        result
+    }
+
+    // Runs as many frames as fit in the time since the last call, for frontends keeping
+    // their own clock, and returns how many ran. After a long stall, like a background
+    // tab, the backlog is dropped instead of fast-forwarded.
+    pub fn advance(&mut self, elapsed: Duration) -> Result<u32, M::Error> {
+        self.pending = (self.pending + elapsed).min(MAX_BACKLOG);
+        let mut frames = 0;
+        while self.pending >= FRAME && !self.quit {
+            self.pending -= FRAME;
+            self.frame()?;
+            frames += 1;
+        }
+        Ok(frames)
+    }
+
+    pub fn run(&mut self) {
+        self.run_with(|_| ())
+    }
+
+    // Runs frames at 60 Hz until the input asks to quit, calling `after` after each.
+    // An error pauses the machine and is left in `error`; resetting starts over.
+    pub fn run_with(&mut self, mut after: impl FnMut(&mut Self)) {
+        let mut next = Instant::now();
+        while !self.quit {
+            if let Err(e) = self.frame() {
+                self.paused = true;
+                self.error = Some(e);
+            }
+            after(self);
+
+            next += FRAME;
+            let now = Instant::now();
+            if next > now {
+                thread::sleep(next - now);
+            } else {
+                next = now;
+            }
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use crate::chip8::{Chip8, Chip8Error, CHIP8_RAM};
+use crate::coverage::{self, READ};
+use crate::screenshot;
+use std::io::{self, Write};
+
+// Ram laid out as a square, one pixel per address, row by row
+pub const HEATMAP_SIZE: usize = 64;
+
+// What's left of a count after a frame. At 0.9 one access fades out in about a
+// second, and the counts of something done every frame level off at 10 times that.
+pub const DEFAULT_DECAY: f32 = 0.9;
+
+// Count at which a channel reaches half brightness
+const HALF_LEVEL: f32 = 4.0;
+
+// Recent ram activity: how often each address was read, written and executed, with
+// older accesses counting less and less. Shows where a rom keeps its state, its
+// sprites and its hot code.
+#[derive(Debug, Clone)]
+pub struct Heatmap {
+    pub reads: Vec<f32>,
+    pub writes: Vec<f32>,
+    pub executes: Vec<f32>,
+    // Multiplies the counts at the end of every frame, 1.0 keeps them forever
+    pub decay: f32,
+}
+
+impl Default for Heatmap {
+    fn default() -> Self {
+        Self::with_decay(DEFAULT_DECAY)
+    }
+}
+
+impl Heatmap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_decay(decay: f32) -> Self {
+        Self {
+            reads: vec![0.0; CHIP8_RAM],
+            writes: vec![0.0; CHIP8_RAM],
+            executes: vec![0.0; CHIP8_RAM],
+            decay,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for counts in [&mut self.reads, &mut self.writes, &mut self.executes] {
+            counts.fill(0.0);
+        }
+    }
+
+    // Runs one frame of `chip8` while counting.
+    pub fn frame(&mut self, chip8: &mut Chip8) -> Result<(), Chip8Error> {
+        let result = chip8.frame_with(|chip8| self.observe(chip8));
+        self.end_frame();
+        result
+    }
+
+    // Counts the instruction `chip8` is about to run, for callers running it themselves.
+    pub fn observe(&mut self, chip8: &Chip8) {
+        if chip8.pc + 1 >= CHIP8_RAM {
+            return;
+        }
+        self.executes[chip8.pc] += 1.0;
+        self.executes[chip8.pc + 1] += 1.0;
+        if let Some((range, flag)) = coverage::data_access(chip8) {
+            let counts = if flag == READ {
+                &mut self.reads
+            } else {
+                &mut self.writes
+            };
+            counts[range].iter_mut().for_each(|count| *count += 1.0);
+        }
+    }
+
+    // Fades the counts, for callers running frames themselves.
+    pub fn end_frame(&mut self) {
+        for counts in [&mut self.reads, &mut self.writes, &mut self.executes] {
+            counts.iter_mut().for_each(|count| *count *= self.decay);
+        }
+    }
+
+    // 64x64 RGBA, address 0 top left: writes in red, reads in green, executes in blue,
+    // each brighter the more often it happened and never quite saturating.
+    pub fn rgba(&self) -> Vec<u8> {
+        (0..CHIP8_RAM)
+            .flat_map(|addr| {
+                [
+                    level(self.writes[addr]),
+                    level(self.reads[addr]),
+                    level(self.executes[addr]),
+                    0xFF,
+                ]
+            })
+            .collect()
+    }
+
+    // RGBA PNG of `rgba`, each address drawn as a `scale` x `scale` block.
+    pub fn write_png<W: Write>(&self, w: W, scale: u32) -> io::Result<()> {
+        let scale = scale.max(1) as usize;
+        let size = HEATMAP_SIZE * scale;
+        let mut encoder = png::Encoder::new(w, size as u32, size as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(screenshot::png_err)?;
+
+        let rgba = self.rgba();
+        let mut data = Vec::with_capacity(size * size * 4);
+        for row in rgba.chunks(HEATMAP_SIZE * 4) {
+            let line: Vec<u8> = row
+                .chunks(4)
+                .flat_map(|px| std::iter::repeat_n(px, scale))
+                .flatten()
+                .copied()
+                .collect();
+            for _ in 0..scale {
+                data.extend_from_slice(&line);
+            }
+        }
+        writer
+            .write_image_data(&data)
+            .map_err(screenshot::png_err)?;
+        writer.finish().map_err(screenshot::png_err)
+    }
+}
+
+fn level(count: f32) -> u8 {
+    (255.0 * count / (count + HALF_LEVEL)) as u8
+}
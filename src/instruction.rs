@@ -0,0 +1,148 @@
+use crate::chip8::Chip8;
+
+// An opcode decoded once, so running it again only takes a match on the variant.
+// Named after Cowgod's mnemonics; x and y are register numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    // 00E0
+    Cls,
+    // 00EE
+    Ret,
+    // 0nnn, ignored
+    Sys,
+    // 1nnn
+    Jp { nnn: u16 },
+    // 2nnn
+    Call { nnn: u16 },
+    // 3xkk
+    SeByte { x: u8, kk: u8 },
+    // 4xkk
+    SneByte { x: u8, kk: u8 },
+    // 5xy0
+    SeReg { x: u8, y: u8 },
+    // 6xkk
+    LdByte { x: u8, kk: u8 },
+    // 7xkk
+    AddByte { x: u8, kk: u8 },
+    // 8xy0 - 8xyE
+    LdReg { x: u8, y: u8 },
+    Or { x: u8, y: u8 },
+    And { x: u8, y: u8 },
+    Xor { x: u8, y: u8 },
+    AddReg { x: u8, y: u8 },
+    Sub { x: u8, y: u8 },
+    Shr { x: u8, y: u8 },
+    Subn { x: u8, y: u8 },
+    Shl { x: u8, y: u8 },
+    // 9xy0
+    SneReg { x: u8, y: u8 },
+    // Annn
+    LdI { nnn: u16 },
+    // Bnnn, x only matters with the jumping quirk
+    JpV0 { x: u8, nnn: u16 },
+    // Cxkk
+    Rnd { x: u8, kk: u8 },
+    // Dxyn
+    Drw { x: u8, y: u8, n: u8 },
+    // Ex9E, ExA1
+    Skp { x: u8 },
+    Sknp { x: u8 },
+    // Fx07 - Fx65
+    LdVxDt { x: u8 },
+    LdVxK { x: u8 },
+    LdDtVx { x: u8 },
+    LdStVx { x: u8 },
+    AddI { x: u8 },
+    LdF { x: u8 },
+    LdB { x: u8 },
+    Store { x: u8 },
+    Load { x: u8 },
+    Invalid { opcode: u16 },
+}
+
+impl Instruction {
+    pub fn decode(opcode: u16) -> Self {
+        let (i, x, y, n, kk, nnn) = Chip8::inst_decode(&opcode);
+        let nnn = nnn as u16;
+        match (i, x, y, n) {
+            (0x00, 0x00, 0x0e, 0x00) => Instruction::Cls,
+            (0x00, 0x00, 0x0e, 0x0e) => Instruction::Ret,
+            (0x00, _, _, _) => Instruction::Sys,
+            (0x01, _, _, _) => Instruction::Jp { nnn },
+            (0x02, _, _, _) => Instruction::Call { nnn },
+            (0x03, _, _, _) => Instruction::SeByte { x, kk },
+            (0x04, _, _, _) => Instruction::SneByte { x, kk },
+            (0x05, _, _, 0x00) => Instruction::SeReg { x, y },
+            (0x06, _, _, _) => Instruction::LdByte { x, kk },
+            (0x07, _, _, _) => Instruction::AddByte { x, kk },
+            (0x08, _, _, 0x00) => Instruction::LdReg { x, y },
+            (0x08, _, _, 0x01) => Instruction::Or { x, y },
+            (0x08, _, _, 0x02) => Instruction::And { x, y },
+            (0x08, _, _, 0x03) => Instruction::Xor { x, y },
+            (0x08, _, _, 0x04) => Instruction::AddReg { x, y },
+            (0x08, _, _, 0x05) => Instruction::Sub { x, y },
+            (0x08, _, _, 0x06) => Instruction::Shr { x, y },
+            (0x08, _, _, 0x07) => Instruction::Subn { x, y },
+            (0x08, _, _, 0x0e) => Instruction::Shl { x, y },
+            (0x09, _, _, 0x00) => Instruction::SneReg { x, y },
+            (0x0a, _, _, _) => Instruction::LdI { nnn },
+            (0x0b, _, _, _) => Instruction::JpV0 { x, nnn },
+            (0x0c, _, _, _) => Instruction::Rnd { x, kk },
+            (0x0d, _, _, _) => Instruction::Drw { x, y, n },
+            (0x0e, _, 0x09, 0x0e) => Instruction::Skp { x },
+            (0x0e, _, 0x0a, 0x01) => Instruction::Sknp { x },
+            (0x0f, _, 0x00, 0x07) => Instruction::LdVxDt { x },
+            (0x0f, _, 0x00, 0x0a) => Instruction::LdVxK { x },
+            (0x0f, _, 0x01, 0x05) => Instruction::LdDtVx { x },
+            (0x0f, _, 0x01, 0x08) => Instruction::LdStVx { x },
+            (0x0f, _, 0x01, 0x0e) => Instruction::AddI { x },
+            (0x0f, _, 0x02, 0x09) => Instruction::LdF { x },
+            (0x0f, _, 0x03, 0x03) => Instruction::LdB { x },
+            (0x0f, _, 0x05, 0x05) => Instruction::Store { x },
+            (0x0f, _, 0x06, 0x05) => Instruction::Load { x },
+            _ => Instruction::Invalid { opcode },
+        }
+    }
+
+    // The opcode pattern as the instruction list writes it, e.g. "Dxyn" or "8xy4"
+    pub fn pattern(&self) -> &'static str {
+        match self {
+            Instruction::Cls => "00E0",
+            Instruction::Ret => "00EE",
+            Instruction::Sys => "0nnn",
+            Instruction::Jp { .. } => "1nnn",
+            Instruction::Call { .. } => "2nnn",
+            Instruction::SeByte { .. } => "3xkk",
+            Instruction::SneByte { .. } => "4xkk",
+            Instruction::SeReg { .. } => "5xy0",
+            Instruction::LdByte { .. } => "6xkk",
+            Instruction::AddByte { .. } => "7xkk",
+            Instruction::LdReg { .. } => "8xy0",
+            Instruction::Or { .. } => "8xy1",
+            Instruction::And { .. } => "8xy2",
+            Instruction::Xor { .. } => "8xy3",
+            Instruction::AddReg { .. } => "8xy4",
+            Instruction::Sub { .. } => "8xy5",
+            Instruction::Shr { .. } => "8xy6",
+            Instruction::Subn { .. } => "8xy7",
+            Instruction::Shl { .. } => "8xyE",
+            Instruction::SneReg { .. } => "9xy0",
+            Instruction::LdI { .. } => "Annn",
+            Instruction::JpV0 { .. } => "Bnnn",
+            Instruction::Rnd { .. } => "Cxkk",
+            Instruction::Drw { .. } => "Dxyn",
+            Instruction::Skp { .. } => "Ex9E",
+            Instruction::Sknp { .. } => "ExA1",
+            Instruction::LdVxDt { .. } => "Fx07",
+            Instruction::LdVxK { .. } => "Fx0A",
+            Instruction::LdDtVx { .. } => "Fx15",
+            Instruction::LdStVx { .. } => "Fx18",
+            Instruction::AddI { .. } => "Fx1E",
+            Instruction::LdF { .. } => "Fx29",
+            Instruction::LdB { .. } => "Fx33",
+            Instruction::Store { .. } => "Fx55",
+            Instruction::Load { .. } => "Fx65",
+            Instruction::Invalid { .. } => "invalid",
+        }
+    }
+}
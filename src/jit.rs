@@ -0,0 +1,600 @@
+use crate::chip8::{Chip8, Chip8Error, CHIP8_RAM};
+use crate::instruction::Instruction;
+use crate::quirks::Quirks;
+use crate::timing::Timing;
+use cranelift_codegen::ir::condcodes::IntCC;
+use cranelift_codegen::ir::{types, AbiParam, InstBuilder, MemFlags, Value};
+use cranelift_codegen::settings::{self, Configurable};
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::{default_libcall_names, Module};
+use std::fmt;
+use std::mem::{self, offset_of};
+
+// Longest run of instructions compiled into one block
+const MAX_BLOCK: usize = 64;
+
+// Blocks compiled before the module is thrown away and started over, so roms that
+// keep rewriting their code don't grow it forever
+const MAX_COMPILED: usize = 1 << 16;
+
+// Times a block can be written over before its address is left to the interpreter,
+// code that changes on every pass costs more to compile than to run
+const MAX_REWRITES: u8 = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JitError {
+    // Cranelift can't generate code for the host
+    Isa(String),
+}
+
+impl fmt::Display for JitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JitError::Isa(e) => write!(f, "no native code generator for this host: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for JitError {}
+
+// The registers blocks work on. Copied out of `Chip8` when a run of blocks starts and
+// back when it stops for the interpreter, so chained blocks pass them on as they are.
+#[repr(C)]
+struct Registers {
+    v: [u8; 16],
+    delay_timer: u8,
+    sound_timer: u8,
+    i: u64,
+    pc: u64,
+}
+
+impl Registers {
+    fn of(chip8: &Chip8) -> Self {
+        Self {
+            v: chip8.v,
+            delay_timer: chip8.delay_timer,
+            sound_timer: chip8.sound_timer,
+            i: chip8.i as u64,
+            pc: chip8.pc as u64,
+        }
+    }
+
+    fn store(&self, chip8: &mut Chip8) {
+        chip8.v = self.v;
+        chip8.delay_timer = self.delay_timer;
+        chip8.sound_timer = self.sound_timer;
+        chip8.i = self.i as usize;
+        chip8.pc = self.pc as usize;
+    }
+}
+
+// Runs at most as many instructions of the block as the second argument says
+type BlockFn = unsafe extern "C" fn(*mut Registers, u64);
+
+#[derive(Clone)]
+struct Block {
+    // None when the instruction at the address can't be compiled
+    func: Option<BlockFn>,
+    // Instructions in the block
+    len: usize,
+    // Left to the interpreter for good after too many rewrites, writes don't drop it
+    kept: bool,
+}
+
+// Runs CHIP-8 code as native code. Straight runs of register instructions (6xkk to
+// 8xyE, Annn, the timers, Fx1E, Fx29) ending at a jump or skip are compiled with
+// Cranelift the first time they are reached and kept by address until something
+// writes over them (Fx33, Fx55, a loaded state, a debugger; see `Chip8::take_written`).
+// Everything touching the screen, keys, stack or ram (Dxyn, Fx0A and the like) runs in
+// the interpreter, so the machine ends up in exactly the state `Chip8::frame` leaves
+// it in.
+pub struct Jit {
+    module: JITModule,
+    blocks: Vec<Option<Block>>,
+    // Blocks covering each byte of ram, so writes elsewhere don't go looking for any
+    covered: Vec<u8>,
+    // Times the block at each address was written over
+    rewrites: Vec<u8>,
+    // What the compiled code assumed, the blocks are dropped when either changes
+    quirks: Quirks,
+    font_addr: usize,
+    // Functions in `module`, live or not
+    in_module: usize,
+    // Blocks compiled so far, including ones compiled again
+    pub compiled: usize,
+}
+
+impl Jit {
+    pub fn new() -> Result<Self, JitError> {
+        Ok(Self {
+            module: new_module()?,
+            blocks: vec![None; CHIP8_RAM],
+            covered: vec![0; CHIP8_RAM],
+            rewrites: vec![0; CHIP8_RAM],
+            quirks: Quirks::default(),
+            font_addr: 0,
+            in_module: 0,
+            compiled: 0,
+        })
+    }
+
+    // Runs one frame of `chip8` like `Chip8::frame`. VIP timing counts cycles per
+    // instruction, so those frames are left to the interpreter.
+    pub fn frame(&mut self, chip8: &mut Chip8) -> Result<(), Chip8Error> {
+        if chip8.timing == Timing::Vip {
+            return chip8.frame();
+        }
+        if chip8.quirks != self.quirks || chip8.profile.font_addr != self.font_addr {
+            self.quirks = chip8.quirks;
+            self.font_addr = chip8.profile.font_addr;
+            self.flush();
+        }
+        self.forget_written(chip8, false);
+        chip8.tick_timers();
+        let result = self.run(chip8, chip8.tick_rate.max(1));
+        chip8.end_frame();
+        result
+    }
+
+    // Drops every compiled block.
+    pub fn flush(&mut self) {
+        self.blocks.fill(None);
+        self.covered.fill(0);
+        self.rewrites.fill(0);
+        if self.in_module > 0 {
+            if let Ok(module) = new_module() {
+                let old = mem::replace(&mut self.module, module);
+                // Nothing points into the old module's code any more
+                unsafe { old.free_memory() };
+                self.in_module = 0;
+            }
+        }
+    }
+
+    fn run(&mut self, chip8: &mut Chip8, mut left: usize) -> Result<(), Chip8Error> {
+        // Taken out of `chip8` for as long as blocks run one after another
+        let mut live: Option<Registers> = None;
+        while left > 0 {
+            let pc = live.as_ref().map_or(chip8.pc, |r| r.pc as usize);
+            if let Some((func, len)) = self.block(chip8, pc) {
+                let registers = live.get_or_insert_with(|| Registers::of(chip8));
+                // Blocks only read and write the registers they're given, and stop
+                // early when the frame ends first
+                unsafe { func(registers, left as u64) };
+                left -= len.min(left);
+                // A jump to itself, which does nothing more for the rest of the frame
+                if len == 1 && registers.pc as usize == pc {
+                    left = 0;
+                }
+                continue;
+            }
+            if let Some(registers) = live.take() {
+                registers.store(chip8);
+            }
+            chip8.exec()?;
+            self.forget_written(chip8, true);
+            left -= 1;
+            // Dxyn waiting for the display or Fx0A for a key, which keeps waiting the
+            // same way until the next frame
+            let opcode = chip8.get_opcode();
+            if chip8.pc == pc && (opcode & 0xF000 == 0xD000 || opcode & 0xF0FF == 0xF00A) {
+                left = 0;
+            }
+        }
+        if let Some(registers) = live {
+            registers.store(chip8);
+        }
+        Ok(())
+    }
+
+    // The compiled block at `pc`, compiling it first if needed.
+    fn block(&mut self, chip8: &Chip8, pc: usize) -> Option<(BlockFn, usize)> {
+        if pc + 1 >= CHIP8_RAM {
+            return None;
+        }
+        if self.blocks[pc].is_none() {
+            if self.in_module >= MAX_COMPILED {
+                self.flush();
+            }
+            let block = if self.rewrites[pc] >= MAX_REWRITES {
+                Block {
+                    func: None,
+                    len: 1,
+                    kept: true,
+                }
+            } else {
+                let block = self.compile(chip8, pc);
+                self.covered[pc..pc + 2 * block.len]
+                    .iter_mut()
+                    .for_each(|n| *n += 1);
+                block
+            };
+            self.blocks[pc] = Some(block);
+        }
+        let block = self.blocks[pc].as_ref()?;
+        block.func.map(|func| (func, block.len))
+    }
+
+    // Drops the blocks compiled from ram that `chip8` has written since the last time.
+    // Only the program's own writes count towards `MAX_REWRITES`, not loading or
+    // resetting the machine.
+    fn forget_written(&mut self, chip8: &mut Chip8, by_program: bool) {
+        let Some(written) = chip8.take_written() else {
+            return;
+        };
+        if self.covered[written.clone()].iter().all(|n| *n == 0) {
+            return;
+        }
+        for pc in written.start.saturating_sub(2 * MAX_BLOCK)..written.end {
+            let Some(block) = self.blocks[pc].as_ref() else {
+                continue;
+            };
+            let end = pc + 2 * block.len;
+            if block.kept || end <= written.start {
+                continue;
+            }
+            self.covered[pc..end].iter_mut().for_each(|n| *n -= 1);
+            self.blocks[pc] = None;
+            if by_program {
+                self.rewrites[pc] = self.rewrites[pc].saturating_add(1);
+            }
+        }
+    }
+
+    fn compile(&mut self, chip8: &Chip8, pc: usize) -> Block {
+        let ram = chip8.ram();
+        let mut instructions = Vec::new();
+        let mut addr = pc;
+        while instructions.len() < MAX_BLOCK && addr + 1 < CHIP8_RAM {
+            let opcode = (ram[addr] as u16) << 8 | ram[addr + 1] as u16;
+            let instruction = Instruction::decode(opcode);
+            if !compiles(instruction) {
+                break;
+            }
+            instructions.push(instruction);
+            addr += 2;
+            if ends_block(instruction) {
+                break;
+            }
+        }
+        let len = instructions.len().max(1);
+        if instructions.is_empty() {
+            return Block {
+                func: None,
+                len,
+                kept: false,
+            };
+        }
+        self.compiled += 1;
+        self.in_module += 1;
+        // A block Cranelift turns down runs in the interpreter instead
+        let func = self.translate(pc, &instructions);
+        Block {
+            func,
+            len,
+            kept: false,
+        }
+    }
+
+    fn translate(&mut self, pc: usize, instructions: &[Instruction]) -> Option<BlockFn> {
+        let mut ctx = self.module.make_context();
+        let pointer = self.module.target_config().pointer_type();
+        ctx.func.signature.params.push(AbiParam::new(pointer));
+        ctx.func.signature.params.push(AbiParam::new(types::I64));
+
+        let mut builder_ctx = FunctionBuilderContext::new();
+        let mut b = FunctionBuilder::new(&mut ctx.func, &mut builder_ctx);
+        let entry = b.create_block();
+        b.append_block_params_for_function_params(entry);
+        b.switch_to_block(entry);
+        b.seal_block(entry);
+        let limit = b.block_params(entry)[1];
+        let mut r = Emitter {
+            registers: b.block_params(entry)[0],
+            b,
+            values: [None; 19],
+            written: [false; 19],
+        };
+
+        // Each instruction reads its registers after the one before has written them,
+        // and in the order the interpreter does, so x or y being F works out the same
+        let mut next_pc = None;
+        for (n, instruction) in instructions.iter().enumerate() {
+            let addr = (pc + 2 * n) as i64;
+            match *instruction {
+                Instruction::Sys => (),
+                Instruction::Jp { nnn } => next_pc = Some(r.b.ins().iconst(types::I64, nnn as i64)),
+                Instruction::SeByte { x, kk } | Instruction::SneByte { x, kk } => {
+                    let cc = if matches!(instruction, Instruction::SeByte { .. }) {
+                        IntCC::Equal
+                    } else {
+                        IntCC::NotEqual
+                    };
+                    let vx = r.get(x);
+                    let cond = r.b.ins().icmp_imm(cc, vx, kk as i64);
+                    next_pc = Some(r.skip(cond, addr));
+                }
+                Instruction::SeReg { x, y } | Instruction::SneReg { x, y } => {
+                    let cc = if matches!(instruction, Instruction::SeReg { .. }) {
+                        IntCC::Equal
+                    } else {
+                        IntCC::NotEqual
+                    };
+                    let (vx, vy) = (r.get(x), r.get(y));
+                    let cond = r.b.ins().icmp(cc, vx, vy);
+                    next_pc = Some(r.skip(cond, addr));
+                }
+                Instruction::LdByte { x, kk } => {
+                    let value = r.b.ins().iconst(types::I8, kk as i64);
+                    r.set(x, value);
+                }
+                Instruction::AddByte { x, kk } => {
+                    let vx = r.get(x);
+                    let value = r.b.ins().iadd_imm(vx, kk as i64);
+                    r.set(x, value);
+                }
+                Instruction::LdReg { x, y } => {
+                    let vy = r.get(y);
+                    r.set(x, vy);
+                }
+                Instruction::Or { x, y }
+                | Instruction::And { x, y }
+                | Instruction::Xor { x, y } => {
+                    let (vx, vy) = (r.get(x), r.get(y));
+                    let value = match instruction {
+                        Instruction::Or { .. } => r.b.ins().bor(vx, vy),
+                        Instruction::And { .. } => r.b.ins().band(vx, vy),
+                        _ => r.b.ins().bxor(vx, vy),
+                    };
+                    r.set(x, value);
+                    if self.quirks.vf_reset {
+                        let zero = r.b.ins().iconst(types::I8, 0);
+                        r.set(0xF, zero);
+                    }
+                }
+                Instruction::AddReg { x, y } => {
+                    let (vx, vy) = (r.get(x), r.get(y));
+                    let vx = r.b.ins().uextend(types::I16, vx);
+                    let vy = r.b.ins().uextend(types::I16, vy);
+                    let sum = r.b.ins().iadd(vx, vy);
+                    let carry = r.b.ins().ushr_imm(sum, 8);
+                    let carry = r.b.ins().ireduce(types::I8, carry);
+                    r.set(0xF, carry);
+                    let sum = r.b.ins().ireduce(types::I8, sum);
+                    r.set(x, sum);
+                }
+                Instruction::Sub { x, y } | Instruction::Subn { x, y } => {
+                    let (from, to) = if matches!(instruction, Instruction::Sub { .. }) {
+                        (x, y)
+                    } else {
+                        (y, x)
+                    };
+                    let (a, c) = (r.get(from), r.get(to));
+                    let no_borrow = r.b.ins().icmp(IntCC::UnsignedGreaterThan, a, c);
+                    r.set(0xF, no_borrow);
+                    let (a, c) = (r.get(from), r.get(to));
+                    let value = r.b.ins().isub(a, c);
+                    r.set(x, value);
+                }
+                Instruction::Shr { x, y } | Instruction::Shl { x, y } => {
+                    let src = if self.quirks.shifting { x } else { y };
+                    let value = r.get(src);
+                    let (shifted, out) = if matches!(instruction, Instruction::Shr { .. }) {
+                        (r.b.ins().ushr_imm(value, 1), r.b.ins().band_imm(value, 1))
+                    } else {
+                        (r.b.ins().ishl_imm(value, 1), r.b.ins().ushr_imm(value, 7))
+                    };
+                    r.set(x, shifted);
+                    r.set(0xF, out);
+                }
+                Instruction::LdI { nnn } => {
+                    let value = r.b.ins().iconst(types::I64, nnn as i64);
+                    r.set(I, value);
+                }
+                Instruction::LdVxDt { x } => {
+                    let value = r.get(DT);
+                    r.set(x, value);
+                }
+                Instruction::LdDtVx { x } => {
+                    let value = r.get(x);
+                    r.set(DT, value);
+                }
+                Instruction::LdStVx { x } => {
+                    let value = r.get(x);
+                    r.set(ST, value);
+                }
+                Instruction::AddI { x } => {
+                    let (vi, vx) = (r.get(I), r.get(x));
+                    let vx = r.b.ins().uextend(types::I64, vx);
+                    let value = r.b.ins().iadd(vi, vx);
+                    let value = r.b.ins().band_imm(value, 0xFFFF);
+                    r.set(I, value);
+                }
+                Instruction::LdF { x } => {
+                    let vx = r.get(x);
+                    let digit = r.b.ins().band_imm(vx, 0x0F);
+                    let digit = r.b.ins().uextend(types::I64, digit);
+                    let offset = r.b.ins().imul_imm(digit, 5);
+                    let value = r.b.ins().iadd_imm(offset, self.font_addr as i64);
+                    r.set(I, value);
+                }
+                _ => unreachable!("{:?} isn't compiled", instruction),
+            }
+            // Only the last instruction can jump or skip
+            if n + 1 < instructions.len() {
+                r.stop_after(limit, n + 1, addr + 2);
+            }
+        }
+
+        let end = (pc + 2 * instructions.len()) as i64;
+        let next_pc = next_pc.unwrap_or_else(|| r.b.ins().iconst(types::I64, end));
+        r.finish(next_pc);
+
+        let id = self
+            .module
+            .declare_anonymous_function(&ctx.func.signature)
+            .ok()?;
+        self.module.define_function(id, &mut ctx).ok()?;
+        self.module.clear_context(&mut ctx);
+        self.module.finalize_definitions().ok()?;
+        let code = self.module.get_finalized_function(id);
+        Some(unsafe { mem::transmute::<*const u8, BlockFn>(code) })
+    }
+}
+
+// Register numbers past VF in `Emitter`
+const I: u8 = 16;
+const DT: u8 = 17;
+const ST: u8 = 18;
+
+// Builds a block's code, which is one straight run with a way out after each
+// instruction, for when the frame ends before the block does. Registers are loaded
+// from `Registers` the first time they are read and only the ones written are stored
+// back.
+struct Emitter<'a> {
+    b: FunctionBuilder<'a>,
+    // Pointer to the `Registers`
+    registers: Value,
+    // V0 - VF, I, DT, ST
+    values: [Option<Value>; 19],
+    written: [bool; 19],
+}
+
+impl Emitter<'_> {
+    fn place(register: u8) -> (types::Type, i32) {
+        match register {
+            I => (types::I64, offset_of!(Registers, i) as i32),
+            DT => (types::I8, offset_of!(Registers, delay_timer) as i32),
+            ST => (types::I8, offset_of!(Registers, sound_timer) as i32),
+            x => (types::I8, (offset_of!(Registers, v) + x as usize) as i32),
+        }
+    }
+
+    fn get(&mut self, register: u8) -> Value {
+        if let Some(value) = self.values[register as usize] {
+            return value;
+        }
+        let (ty, offset) = Self::place(register);
+        let value = self
+            .b
+            .ins()
+            .load(ty, MemFlags::trusted(), self.registers, offset);
+        self.values[register as usize] = Some(value);
+        value
+    }
+
+    fn set(&mut self, register: u8, value: Value) {
+        self.values[register as usize] = Some(value);
+        self.written[register as usize] = true;
+    }
+
+    // The pc after a skip at `addr`
+    fn skip(&mut self, cond: Value, addr: i64) -> Value {
+        let skipped = self.b.ins().iconst(types::I64, addr + 4);
+        let not_skipped = self.b.ins().iconst(types::I64, addr + 2);
+        self.b.ins().select(cond, skipped, not_skipped)
+    }
+
+    // Returns at `pc` when `limit` is `run`, the instructions run so far
+    fn stop_after(&mut self, limit: Value, run: usize, pc: i64) {
+        let stop = self.b.create_block();
+        let go_on = self.b.create_block();
+        let done = self.b.ins().icmp_imm(IntCC::Equal, limit, run as i64);
+        self.b.ins().brif(done, stop, &[], go_on, &[]);
+        self.b.switch_to_block(stop);
+        self.b.seal_block(stop);
+        let pc = self.b.ins().iconst(types::I64, pc);
+        self.store(pc);
+        self.b.switch_to_block(go_on);
+        self.b.seal_block(go_on);
+    }
+
+    fn finish(mut self, next_pc: Value) {
+        self.store(next_pc);
+        self.b.finalize();
+    }
+
+    // Stores the registers written so far and `next_pc`, and returns
+    fn store(&mut self, next_pc: Value) {
+        for register in 0..19 {
+            if let (true, Some(value)) = (
+                self.written[register as usize],
+                self.values[register as usize],
+            ) {
+                let (_, offset) = Self::place(register);
+                self.b
+                    .ins()
+                    .store(MemFlags::trusted(), value, self.registers, offset);
+            }
+        }
+        let offset = offset_of!(Registers, pc) as i32;
+        self.b
+            .ins()
+            .store(MemFlags::trusted(), next_pc, self.registers, offset);
+        self.b.ins().return_(&[]);
+    }
+}
+
+fn new_module() -> Result<JITModule, JitError> {
+    let mut flags = settings::builder();
+    let mut set = |name, value| {
+        flags
+            .set(name, value)
+            .map_err(|e| JitError::Isa(e.to_string()))
+    };
+    set("use_colocated_libcalls", "false")?;
+    set("is_pic", "false")?;
+    set("opt_level", "speed")?;
+    set("enable_verifier", "false")?;
+    let isa = cranelift_native::builder()
+        .map_err(|e| JitError::Isa(e.to_string()))?
+        .finish(settings::Flags::new(flags))
+        .map_err(|e| JitError::Isa(e.to_string()))?;
+    Ok(JITModule::new(JITBuilder::with_isa(
+        isa,
+        default_libcall_names(),
+    )))
+}
+
+// Instructions that only touch V, I, pc and the timers
+fn compiles(instruction: Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::Sys
+            | Instruction::Jp { .. }
+            | Instruction::SeByte { .. }
+            | Instruction::SneByte { .. }
+            | Instruction::SeReg { .. }
+            | Instruction::LdByte { .. }
+            | Instruction::AddByte { .. }
+            | Instruction::LdReg { .. }
+            | Instruction::Or { .. }
+            | Instruction::And { .. }
+            | Instruction::Xor { .. }
+            | Instruction::AddReg { .. }
+            | Instruction::Sub { .. }
+            | Instruction::Shr { .. }
+            | Instruction::Subn { .. }
+            | Instruction::Shl { .. }
+            | Instruction::SneReg { .. }
+            | Instruction::LdI { .. }
+            | Instruction::LdVxDt { .. }
+            | Instruction::LdDtVx { .. }
+            | Instruction::LdStVx { .. }
+            | Instruction::AddI { .. }
+            | Instruction::LdF { .. }
+    )
+}
+
+// Jumps and skips, after which the pc depends on the block
+fn ends_block(instruction: Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::Jp { .. }
+            | Instruction::SeByte { .. }
+            | Instruction::SneByte { .. }
+            | Instruction::SeReg { .. }
+            | Instruction::SneReg { .. }
+    )
+}
@@ -0,0 +1,268 @@
+use crate::rom::Rom;
+use serde::{de, Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+// How far an analog stick has to be pushed, out of i16::MAX, to count as a key press
+pub const KEYMAP_AXIS_THRESHOLD: i16 = 16384;
+
+#[derive(Debug)]
+pub enum KeyMapError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for KeyMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyMapError::Io(e) => write!(f, "could not read key bindings: {}", e),
+            KeyMapError::Toml(e) => write!(f, "{}", e),
+            KeyMapError::Json(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for KeyMapError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            KeyMapError::Io(e) => Some(e),
+            KeyMapError::Toml(e) => Some(e),
+            KeyMapError::Json(e) => Some(e),
+        }
+    }
+}
+
+// Host input to CHIP-8 key bindings, shared by all frontends and stored as TOML or JSON:
+//
+//     [keys]
+//     1 = 1
+//     q = 4
+//     up = 2
+//
+//     [buttons]
+//     a = 5
+//     dpup = 2
+//
+//     [axes]
+//     "leftx-" = 4
+//
+//     [roms.<sha1>.keys]
+//     space = 5
+//
+// Key names are SDL key names ("q", "1", "up", "space", "return"), button and axis names
+// are SDL game controller names ("a", "dpup", "leftshoulder", "leftx", "righttrigger"),
+// axes with a "+" or "-" for the direction. Names are matched case-insensitively.
+// Entries under `roms` apply on top of the rest when that rom is loaded.
+// A file holds the complete map: sections left out have no bindings. Bindings to
+// anything but a CHIP-8 key (0 - 15) fail to load.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyMap {
+    #[serde(default, deserialize_with = "chip8_keys")]
+    pub keys: BTreeMap<String, u8>,
+    #[serde(default, deserialize_with = "chip8_keys")]
+    pub buttons: BTreeMap<String, u8>,
+    #[serde(default, deserialize_with = "chip8_keys")]
+    pub axes: BTreeMap<String, u8>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub roms: BTreeMap<String, KeyMap>,
+}
+
+impl Default for KeyMap {
+    // 1 2 3 4      1 2 3 C
+    // Q W E R  ->  4 5 6 D
+    // A S D F      7 8 9 E
+    // Z X C V      A 0 B F
+    //
+    // with the d-pad and left stick on 2/4/6/8, the usual CHIP-8 directions.
+    fn default() -> Self {
+        let keys = [
+            ("1", 0x1),
+            ("2", 0x2),
+            ("3", 0x3),
+            ("4", 0xC),
+            ("q", 0x4),
+            ("w", 0x5),
+            ("e", 0x6),
+            ("r", 0xD),
+            ("a", 0x7),
+            ("s", 0x8),
+            ("d", 0x9),
+            ("f", 0xE),
+            ("z", 0xA),
+            ("x", 0x0),
+            ("c", 0xB),
+            ("v", 0xF),
+        ];
+        let buttons = [
+            ("dpup", 0x2),
+            ("dpdown", 0x8),
+            ("dpleft", 0x4),
+            ("dpright", 0x6),
+            ("a", 0x5),
+            ("b", 0x0),
+            ("x", 0xA),
+            ("y", 0xB),
+            ("start", 0xF),
+            ("back", 0xE),
+        ];
+        let axes = [
+            ("lefty-", 0x2),
+            ("lefty+", 0x8),
+            ("leftx-", 0x4),
+            ("leftx+", 0x6),
+        ];
+        let map = |entries: &[(&str, u8)]| {
+            entries
+                .iter()
+                .map(|(name, key)| (name.to_string(), *key))
+                .collect()
+        };
+        Self {
+            keys: map(&keys),
+            buttons: map(&buttons),
+            axes: map(&axes),
+            roms: BTreeMap::new(),
+        }
+    }
+}
+
+impl KeyMap {
+    // No bindings at all, to build a map from scratch.
+    pub fn empty() -> Self {
+        Self {
+            keys: BTreeMap::new(),
+            buttons: BTreeMap::new(),
+            axes: BTreeMap::new(),
+            roms: BTreeMap::new(),
+        }
+    }
+
+    // JSON for a .json file, TOML for anything else
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, KeyMapError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(KeyMapError::Io)?;
+        if path.extension().is_some_and(|ext| ext == "json") {
+            Self::from_json(&text).map_err(KeyMapError::Json)
+        } else {
+            Self::from_toml(&text).map_err(KeyMapError::Toml)
+        }
+    }
+
+    pub fn from_toml(s: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(s)
+    }
+
+    pub fn to_toml(&self) -> Result<String, toml::ser::Error> {
+        toml::to_string_pretty(self)
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(s)
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn key(&self, name: &str) -> Option<u8> {
+        lookup(&self.keys, name)
+    }
+
+    pub fn button(&self, name: &str) -> Option<u8> {
+        lookup(&self.buttons, name)
+    }
+
+    // `value` as reported by the controller; None while the stick is within the threshold.
+    pub fn axis(&self, name: &str, value: i16) -> Option<u8> {
+        if value >= KEYMAP_AXIS_THRESHOLD {
+            lookup(&self.axes, &format!("{}+", name))
+        } else if value <= -KEYMAP_AXIS_THRESHOLD {
+            lookup(&self.axes, &format!("{}-", name))
+        } else {
+            None
+        }
+    }
+
+    // The bindings for `rom`, with its overrides applied (looked up by SHA-1).
+    pub fn for_rom(&self, rom: &Rom) -> KeyMap {
+        let mut map = KeyMap {
+            roms: BTreeMap::new(),
+            ..self.clone()
+        };
+        let sha1 = rom.sha1_hex();
+        if let Some((_, overrides)) = self
+            .roms
+            .iter()
+            .find(|(hash, _)| hash.eq_ignore_ascii_case(&sha1))
+        {
+            map.merge(overrides);
+        }
+        map
+    }
+
+    // Binds the named controls of a rom database entry ("up", "down", "left", "right",
+    // "a", "b") to the arrow keys, d-pad, left stick and face buttons. Controls bound
+    // to anything but a CHIP-8 key are left out.
+    pub fn with_rom_controls(mut self, controls: &BTreeMap<String, u8>) -> Self {
+        for (control, key) in controls.iter().filter(|(_, key)| **key <= 0xF) {
+            let (keys, buttons, axes): (&[&str], &[&str], &[&str]) = match control.as_str() {
+                "up" => (&["up"], &["dpup"], &["lefty-"]),
+                "down" => (&["down"], &["dpdown"], &["lefty+"]),
+                "left" => (&["left"], &["dpleft"], &["leftx-"]),
+                "right" => (&["right"], &["dpright"], &["leftx+"]),
+                "a" => (&["space"], &["a"], &[]),
+                "b" => (&["left shift"], &["b"], &[]),
+                _ => continue,
+            };
+            keys.iter().for_each(|k| insert(&mut self.keys, k, *key));
+            buttons
+                .iter()
+                .for_each(|b| insert(&mut self.buttons, b, *key));
+            axes.iter().for_each(|a| insert(&mut self.axes, a, *key));
+        }
+        self
+    }
+
+    fn merge(&mut self, other: &KeyMap) {
+        other
+            .keys
+            .iter()
+            .for_each(|(k, v)| insert(&mut self.keys, k, *v));
+        other
+            .buttons
+            .iter()
+            .for_each(|(k, v)| insert(&mut self.buttons, k, *v));
+        other
+            .axes
+            .iter()
+            .for_each(|(k, v)| insert(&mut self.axes, k, *v));
+    }
+}
+
+fn lookup(map: &BTreeMap<String, u8>, name: &str) -> Option<u8> {
+    map.iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(name))
+        .map(|(_, v)| *v)
+}
+
+fn chip8_keys<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeMap<String, u8>, D::Error> {
+    let map = BTreeMap::<String, u8>::deserialize(deserializer)?;
+    match map.iter().find(|(_, key)| **key > 0xF) {
+        Some((name, key)) => Err(de::Error::custom(format!(
+            "'{}' is bound to {}, CHIP-8 keys go from 0 to 15",
+            name, key
+        ))),
+        None => Ok(map),
+    }
+}
+
+// Replaces any existing binding for the same name, whatever its case.
+fn insert(map: &mut BTreeMap<String, u8>, name: &str, key: u8) {
+    map.retain(|k, _| !k.eq_ignore_ascii_case(name));
+    map.insert(name.to_ascii_lowercase(), key);
+}
@@ -1 +1,23 @@
+pub mod analysis;
 pub mod chip8;
+pub mod config;
+pub mod coverage;
+pub mod disasm;
+pub mod emulator;
+pub mod font;
+pub mod heatmap;
+pub mod instruction;
+#[cfg(feature = "jit")]
+pub mod jit;
+pub mod keymap;
+pub mod machine;
+pub mod palette;
+pub mod profile;
+pub mod profiler;
+pub mod quirks;
+pub mod recorder;
+pub mod rom;
+pub mod romdb;
+pub mod screenshot;
+pub mod state;
+pub mod timing;
@@ -0,0 +1,114 @@
+use crate::chip8::{Chip8, Chip8Error, CHIP8_SCREEN_HEIGHT, CHIP8_SCREEN_WIDTH};
+use crate::state::StateError;
+use std::slice::Chunks;
+
+// What a machine shows, borrowed from it. Sizes differ between variants (and can change
+// while a rom runs, e.g. when SUPER-CHIP switches to high resolution), so frontends
+// should size their output from every frame rather than once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Framebuffer<'a> {
+    pub width: usize,
+    pub height: usize,
+    // Bitplanes: a pixel holds one bit per plane, so with one plane it's 0 or 1
+    pub planes: usize,
+    // Row by row, one byte per pixel
+    pub pixels: &'a [u8],
+}
+
+impl<'a> Framebuffer<'a> {
+    pub fn rows(&self) -> Chunks<'a, u8> {
+        self.pixels.chunks(self.width)
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> u8 {
+        self.pixels[y * self.width + x]
+    }
+}
+
+// Everything a frontend needs to run a CHIP-8 style machine, so the same frontend runs
+// any variant. Configuration that only makes sense for one (quirks, profiles, debugger
+// access to registers) stays on the concrete type.
+pub trait Machine {
+    type Error: std::error::Error;
+
+    // A single instruction, without ticking the timers
+    fn step(&mut self) -> Result<(), Self::Error>;
+    // One 60 Hz frame: the timers tick once and a frame's worth of instructions run
+    fn frame(&mut self) -> Result<(), Self::Error>;
+    fn tick_timers(&mut self);
+
+    // Keys are 0x0 - 0xF
+    fn key_down(&mut self, key: usize);
+    fn key_up(&mut self, key: usize);
+
+    fn framebuffer(&self) -> Framebuffer<'_>;
+    // Whether the buzzer sounds
+    fn sound(&self) -> bool;
+
+    fn save_state(&self) -> Vec<u8>;
+    fn load_state(&mut self, state: &[u8]) -> Result<(), StateError>;
+    // Power cycle, with the loaded rom kept
+    fn reset(&mut self);
+
+    // Instructions per frame, for speed controls. Machines with their own timing may
+    // ignore it.
+    fn speed(&self) -> usize;
+    fn set_speed(&mut self, speed: usize);
+}
+
+impl Machine for Chip8 {
+    type Error = Chip8Error;
+
+    fn step(&mut self) -> Result<(), Chip8Error> {
+        self.exec()
+    }
+
+    fn frame(&mut self) -> Result<(), Chip8Error> {
+        Chip8::frame(self)
+    }
+
+    fn tick_timers(&mut self) {
+        Chip8::tick_timers(self)
+    }
+
+    fn key_down(&mut self, key: usize) {
+        Chip8::key_down(self, key)
+    }
+
+    fn key_up(&mut self, key: usize) {
+        Chip8::key_up(self, key)
+    }
+
+    fn framebuffer(&self) -> Framebuffer<'_> {
+        Framebuffer {
+            width: CHIP8_SCREEN_WIDTH,
+            height: CHIP8_SCREEN_HEIGHT,
+            planes: 1,
+            pixels: self.screen.as_flattened(),
+        }
+    }
+
+    fn sound(&self) -> bool {
+        self.sound_timer > 0
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        Chip8::save_state(self)
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        Chip8::load_state(self, state)
+    }
+
+    fn reset(&mut self) {
+        Chip8::reset(self)
+    }
+
+    fn speed(&self) -> usize {
+        self.tick_rate
+    }
+
+    fn set_speed(&mut self, speed: usize) {
+        self.tick_rate = speed.max(1);
+    }
+}
@@ -0,0 +1,121 @@
+use crate::romdb::RomColors;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    pub background: [u8; 3],
+    pub foreground: [u8; 3],
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            background: [0x00, 0x00, 0x00],
+            foreground: [0xFF, 0xFF, 0xFF],
+        }
+    }
+}
+
+impl Palette {
+    pub const PRESETS: [(&'static str, Palette); 5] = [
+        (
+            "mono",
+            Palette {
+                background: [0x00, 0x00, 0x00],
+                foreground: [0xFF, 0xFF, 0xFF],
+            },
+        ),
+        (
+            "octo",
+            Palette {
+                background: [0x99, 0x66, 0x00],
+                foreground: [0xFF, 0xCC, 0x00],
+            },
+        ),
+        (
+            "amber",
+            Palette {
+                background: [0x1A, 0x0F, 0x00],
+                foreground: [0xFF, 0xB0, 0x00],
+            },
+        ),
+        (
+            "green",
+            Palette {
+                background: [0x00, 0x14, 0x00],
+                foreground: [0x33, 0xFF, 0x33],
+            },
+        ),
+        (
+            "lcd",
+            Palette {
+                background: [0x9B, 0xBC, 0x0F],
+                foreground: [0x0F, 0x38, 0x0F],
+            },
+        ),
+    ];
+
+    pub fn color(&self, pixel: u8) -> [u8; 3] {
+        if pixel == 0 {
+            self.background
+        } else {
+            self.foreground
+        }
+    }
+
+    // The first two colors of a rom database entry, if it has them.
+    pub fn from_rom_colors(colors: &RomColors) -> Option<Self> {
+        match colors.pixels.as_slice() {
+            [background, foreground, ..] => Some(Self {
+                background: *background,
+                foreground: *foreground,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Palette {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [br, bg, bb] = self.background;
+        let [fr, fg, fb] = self.foreground;
+        write!(
+            f,
+            "{:02x}{:02x}{:02x},{:02x}{:02x}{:02x}",
+            br, bg, bb, fr, fg, fb
+        )
+    }
+}
+
+// A preset name, or background and foreground as hex: "000000,ffffff"
+impl FromStr for Palette {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((_, palette)) = Self::PRESETS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(s))
+        {
+            return Ok(*palette);
+        }
+        let parse = |hex: &str| -> Result<[u8; 3], String> {
+            let hex = hex.trim().trim_start_matches('#');
+            match u32::from_str_radix(hex, 16) {
+                Ok(v) if hex.len() == 6 => Ok([(v >> 16) as u8, (v >> 8) as u8, v as u8]),
+                _ => Err(format!("invalid color '{}'", hex)),
+            }
+        };
+        match s.split_once(',') {
+            Some((background, foreground)) => Ok(Self {
+                background: parse(background)?,
+                foreground: parse(foreground)?,
+            }),
+            None => Err(format!(
+                "unknown palette '{}', expected one of {} or <background>,<foreground> in hex",
+                s,
+                Self::PRESETS.map(|(name, _)| name).join(", ")
+            )),
+        }
+    }
+}
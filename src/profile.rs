@@ -0,0 +1,199 @@
+use crate::chip8::{
+    CHIP8_FONTSET, CHIP8_FONT_ADDR, CHIP8_MAX_STACK_DEPTH, CHIP8_RAM, CHIP8_STACK_DEPTH,
+    CHIP8_START_ADDR,
+};
+use crate::font::FontSet;
+use std::fmt;
+use std::str::FromStr;
+
+// Memory layout of the machine a rom was written for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Profile {
+    // Where the rom is copied into ram
+    pub load_addr: usize,
+    // Initial program counter, usually the same as `load_addr`
+    pub start_pc: usize,
+    // Where the hexadecimal font is written, and where Fx29 points I
+    pub font_addr: usize,
+    pub font: [u8; 80],
+    pub stack: StackLayout,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self::chip8()
+    }
+}
+
+impl Profile {
+    pub fn chip8() -> Self {
+        Self {
+            load_addr: CHIP8_START_ADDR,
+            start_pc: CHIP8_START_ADDR,
+            font_addr: CHIP8_FONT_ADDR,
+            font: CHIP8_FONTSET,
+            stack: StackLayout::default(),
+        }
+    }
+
+    // The VIP's font lived in the interpreter's rom; emulated here at 0x000,
+    // which is also where some test roms look for it. Its interpreter kept 12 return
+    // addresses in ram at 0xEA0, where a rom can overwrite them.
+    pub fn vip() -> Self {
+        Self {
+            font_addr: 0x000,
+            font: FontSet::Vip.glyphs(),
+            stack: StackLayout::in_ram(12, 0xEA0),
+            ..Self::chip8()
+        }
+    }
+
+    // ETI-660 programs are loaded and start at 0x600, below that is the interpreter and its font
+    pub fn eti660() -> Self {
+        Self {
+            load_addr: 0x600,
+            start_pc: 0x600,
+            font_addr: 0x000,
+            font: FontSet::Eti660.glyphs(),
+            stack: StackLayout::default(),
+        }
+    }
+
+    // For hybrid roms that carry their own layout, e.g. a machine code prologue
+    // at the load address with the interpreted program starting further in.
+    pub fn custom(load_addr: usize, start_pc: usize) -> Self {
+        Self::chip8().with_load_addr(load_addr, start_pc)
+    }
+
+    pub fn with_load_addr(self, load_addr: usize, start_pc: usize) -> Self {
+        Self {
+            load_addr,
+            start_pc,
+            ..self
+        }
+    }
+
+    pub fn with_font(self, font: FontSet, font_addr: usize) -> Self {
+        Self {
+            font: font.glyphs(),
+            font_addr,
+            ..self
+        }
+    }
+
+    pub fn with_stack(self, stack: StackLayout) -> Self {
+        Self { stack, ..self }
+    }
+
+    // The font and the stack inside ram
+    pub(crate) fn fit(self) -> Self {
+        Self {
+            font_addr: self.font_addr.min(CHIP8_RAM - self.font.len()),
+            stack: self.stack.fit(),
+            ..self
+        }
+    }
+}
+
+// The presets by name: "chip8", "vip" or "eti660"
+impl FromStr for Profile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "chip8" | "default" => Ok(Self::chip8()),
+            "vip" => Ok(Self::vip()),
+            "eti660" => Ok(Self::eti660()),
+            _ => Err(format!("unknown profile '{}'", s)),
+        }
+    }
+}
+
+// How many return addresses 2nnn can push, and where they're kept: outside ram where
+// nothing but 2nnn and 00EE can touch them, or in ram like on the VIP, two bytes per
+// level (high byte first) from `addr` up, where e.g. Fx55 can overwrite them.
+// Calls past `depth` fail with a stack overflow, returns with none left with an underflow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StackLayout {
+    pub depth: usize,
+    pub addr: Option<usize>,
+}
+
+impl Default for StackLayout {
+    fn default() -> Self {
+        Self::new(CHIP8_STACK_DEPTH)
+    }
+}
+
+impl StackLayout {
+    pub fn new(depth: usize) -> Self {
+        Self { depth, addr: None }
+    }
+
+    pub fn in_ram(depth: usize, addr: usize) -> Self {
+        Self {
+            depth,
+            addr: Some(addr),
+        }
+    }
+
+    // Bytes of ram from `addr` the stack takes up, 0 when it's outside ram
+    pub fn ram_len(&self) -> usize {
+        self.addr.map_or(0, |_| self.depth * 2)
+    }
+
+    // At least one level and at most CHIP8_MAX_STACK_DEPTH, all of them inside ram
+    pub(crate) fn fit(self) -> Self {
+        let mut depth = self.depth.clamp(1, CHIP8_MAX_STACK_DEPTH);
+        let addr = self.addr.map(|addr| addr.min(CHIP8_RAM - 2));
+        if let Some(addr) = addr {
+            depth = depth.min((CHIP8_RAM - addr) / 2);
+        }
+        Self { depth, addr }
+    }
+}
+
+impl fmt::Display for StackLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.addr {
+            Some(addr) => write!(f, "{}@{:#05x}", self.depth, addr),
+            None => write!(f, "{}", self.depth),
+        }
+    }
+}
+
+// "<depth>" for a stack outside ram, "<depth>@<hex address>" for one in ram, e.g. "16"
+// or "12@ea0"
+impl FromStr for StackLayout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (depth, addr) = match s.split_once('@') {
+            Some((depth, addr)) => (depth, Some(addr)),
+            None => (s, None),
+        };
+        let depth: usize = depth
+            .trim()
+            .parse()
+            .map_err(|_| format!("bad stack depth '{}'", depth))?;
+        if !(1..=CHIP8_MAX_STACK_DEPTH).contains(&depth) {
+            return Err(format!(
+                "stack depth must be 1 to {}",
+                CHIP8_MAX_STACK_DEPTH
+            ));
+        }
+        let Some(addr) = addr else {
+            return Ok(Self::new(depth));
+        };
+        let digits = addr.trim().trim_start_matches("0x");
+        let addr = usize::from_str_radix(digits, 16)
+            .map_err(|_| format!("bad stack address '{}'", addr))?;
+        if addr + depth * 2 > CHIP8_RAM {
+            return Err(format!(
+                "a stack {} deep at {:#05x} doesn't fit in ram",
+                depth, addr
+            ));
+        }
+        Ok(Self::in_ram(depth, addr))
+    }
+}
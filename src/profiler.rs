@@ -0,0 +1,226 @@
+use crate::chip8::{Chip8, Chip8Error, CHIP8_RAM};
+use crate::instruction::Instruction;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write};
+
+// Addresses listed in the flat report
+const HOT_SPOTS: usize = 20;
+
+// Where the time goes while a rom runs: instructions per address and per kind of
+// opcode, per subroutine and per call stack, and frames spent waiting in Fx0A.
+// Every instruction counts as one cycle.
+//
+// Subroutines are told apart by watching the stack pointer: when it goes up, the
+// instruction about to run is the entry of the subroutine just called.
+#[derive(Debug, Clone)]
+pub struct Profiler {
+    pub instructions: u64,
+    pub frames: u64,
+    // Frames that ended with Fx0A still waiting for a key
+    pub key_wait_frames: u64,
+    // Instructions run at each address
+    pub by_addr: Vec<u64>,
+    // By opcode pattern, e.g. "Dxyn" or "8xy4"
+    pub by_family: BTreeMap<&'static str, u64>,
+    // By entry address; None is the code outside any subroutine
+    pub subroutines: BTreeMap<Option<usize>, SubroutineStats>,
+    // (caller, callee) to how often
+    pub calls: BTreeMap<(Option<usize>, usize), u64>,
+    // Instructions per call stack, outermost subroutine first
+    pub stacks: HashMap<Vec<usize>, u64>,
+    // Last opcode run at each address, for the reports
+    opcodes: Vec<u16>,
+    // Entries of the subroutines currently called
+    stack: Vec<usize>,
+    last: Option<(usize, Instruction)>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SubroutineStats {
+    pub calls: u64,
+    // In the subroutine itself
+    pub self_cycles: u64,
+    // Including what it called
+    pub total_cycles: u64,
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self {
+            instructions: 0,
+            frames: 0,
+            key_wait_frames: 0,
+            by_addr: vec![0; CHIP8_RAM],
+            by_family: BTreeMap::new(),
+            subroutines: BTreeMap::new(),
+            calls: BTreeMap::new(),
+            stacks: HashMap::new(),
+            opcodes: vec![0; CHIP8_RAM],
+            stack: Vec::new(),
+            last: None,
+        }
+    }
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Runs one frame of `chip8` while counting.
+    pub fn frame(&mut self, chip8: &mut Chip8) -> Result<(), Chip8Error> {
+        let result = chip8.frame_with(|chip8| self.observe(chip8));
+        self.end_frame(chip8);
+        result
+    }
+
+    // Counts the instruction `chip8` is about to run, for callers running it themselves.
+    pub fn observe(&mut self, chip8: &Chip8) {
+        if chip8.pc + 1 >= CHIP8_RAM {
+            return;
+        }
+        while self.stack.len() < chip8.sp {
+            let caller = self.stack.last().copied();
+            self.stack.push(chip8.pc);
+            *self.calls.entry((caller, chip8.pc)).or_default() += 1;
+            self.subroutines.entry(Some(chip8.pc)).or_default().calls += 1;
+        }
+        self.stack.truncate(chip8.sp);
+
+        let opcode = chip8.get_opcode();
+        let instruction = Instruction::decode(opcode);
+        self.instructions += 1;
+        self.by_addr[chip8.pc] += 1;
+        self.opcodes[chip8.pc] = opcode;
+        *self.by_family.entry(instruction.pattern()).or_default() += 1;
+
+        let current = self.stack.last().copied();
+        self.subroutines.entry(current).or_default().self_cycles += 1;
+        self.subroutines.entry(None).or_default().total_cycles += 1;
+        // Once per subroutine, however often recursion put it on the stack
+        for (depth, entry) in self.stack.iter().enumerate() {
+            if !self.stack[..depth].contains(entry) {
+                self.subroutines
+                    .entry(Some(*entry))
+                    .or_default()
+                    .total_cycles += 1;
+            }
+        }
+        match self.stacks.get_mut(self.stack.as_slice()) {
+            Some(count) => *count += 1,
+            None => {
+                self.stacks.insert(self.stack.clone(), 1);
+            }
+        }
+        self.last = Some((chip8.pc, instruction));
+    }
+
+    // Closes the frame `chip8` just ran, for callers running it themselves.
+    pub fn end_frame(&mut self, chip8: &Chip8) {
+        self.frames += 1;
+        if let Some((addr, instruction)) = self.last {
+            if matches!(instruction, Instruction::LdVxK { .. }) && chip8.pc == addr {
+                self.key_wait_frames += 1;
+            }
+        }
+    }
+
+    // Totals, the busiest addresses and the opcode kinds.
+    pub fn write_flat<W: Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(
+            w,
+            "{} instructions in {} frames, {} frames waiting for a key (Fx0A)",
+            self.instructions, self.frames, self.key_wait_frames
+        )?;
+        writeln!(
+            w,
+            "\nhot spots\n{:>10} {:>7}  {:<5}  opcode",
+            "count", "%", "addr"
+        )?;
+        let mut addrs: Vec<usize> = (0..CHIP8_RAM).filter(|a| self.by_addr[*a] > 0).collect();
+        addrs.sort_by_key(|a| std::cmp::Reverse(self.by_addr[*a]));
+        for addr in addrs.into_iter().take(HOT_SPOTS) {
+            writeln!(
+                w,
+                "{:>10} {:>6.2}%  {:#05x}  {:04x}",
+                self.by_addr[addr],
+                self.percent(self.by_addr[addr]),
+                addr,
+                self.opcodes[addr]
+            )?;
+        }
+        writeln!(w, "\nopcodes\n{:>10} {:>7}  opcode", "count", "%")?;
+        let mut families: Vec<(&str, u64)> = self.by_family.iter().map(|(f, n)| (*f, *n)).collect();
+        families.sort_by_key(|(_, n)| std::cmp::Reverse(*n));
+        for (family, count) in families {
+            writeln!(w, "{:>10} {:>6.2}%  {}", count, self.percent(count), family)?;
+        }
+        Ok(())
+    }
+
+    // Each subroutine with its own and total cycles, where it's called from and what it calls.
+    pub fn write_call_graph<W: Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(
+            w,
+            "{:<12} {:>8} {:>10} {:>7} {:>10} {:>7}",
+            "subroutine", "calls", "self", "%", "total", "%"
+        )?;
+        let mut subroutines: Vec<_> = self.subroutines.iter().collect();
+        subroutines.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.total_cycles));
+        for (entry, stats) in subroutines {
+            writeln!(
+                w,
+                "{:<12} {:>8} {:>10} {:>6.2}% {:>10} {:>6.2}%",
+                name(*entry),
+                stats.calls,
+                stats.self_cycles,
+                self.percent(stats.self_cycles),
+                stats.total_cycles,
+                self.percent(stats.total_cycles)
+            )?;
+            for ((caller, _), count) in self.calls.iter().filter(|((_, c), _)| Some(*c) == *entry) {
+                writeln!(w, "    called from {} ({})", name(*caller), count)?;
+            }
+            for ((_, callee), count) in self.calls.iter().filter(|((c, _), _)| c == entry) {
+                writeln!(w, "    calls {} ({})", name(Some(*callee)), count)?;
+            }
+        }
+        Ok(())
+    }
+
+    // One "main;sub_0x240;sub_0x2a0 <count>" line per call stack, as flamegraph.pl,
+    // inferno and speedscope read them.
+    pub fn write_folded<W: Write>(&self, mut w: W) -> io::Result<()> {
+        let mut stacks: Vec<(String, u64)> = self
+            .stacks
+            .iter()
+            .map(|(stack, count)| {
+                let names: Vec<String> = std::iter::once(None)
+                    .chain(stack.iter().map(|entry| Some(*entry)))
+                    .map(name)
+                    .collect();
+                (names.join(";"), *count)
+            })
+            .collect();
+        stacks.sort();
+        for (stack, count) in stacks {
+            writeln!(w, "{} {}", stack, count)?;
+        }
+        Ok(())
+    }
+
+    fn percent(&self, count: u64) -> f64 {
+        if self.instructions == 0 {
+            0.0
+        } else {
+            count as f64 * 100.0 / self.instructions as f64
+        }
+    }
+}
+
+fn name(entry: Option<usize>) -> String {
+    match entry {
+        None => "main".into(),
+        Some(addr) => format!("sub_{:#05x}", addr),
+    }
+}
@@ -0,0 +1,114 @@
+use crate::rom::Platform;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+// Behaviours that differ between CHIP-8 interpreters. The default matches what this
+// emulator has always done; the presets follow https://github.com/Timendus/chip8-test-suite
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Quirks {
+    // 8xy1, 8xy2, 8xy3 reset VF to 0
+    pub vf_reset: bool,
+    // Fx55 and Fx65 leave I pointing past the last register stored or loaded
+    pub memory: bool,
+    // Dxyn waits for the next 60 Hz frame, so at most one sprite is drawn per frame
+    pub display_wait: bool,
+    // Sprites are cut off at the screen edges instead of wrapping around
+    pub clipping: bool,
+    // 8xy6 and 8xyE shift Vx in place instead of shifting Vy into Vx
+    pub shifting: bool,
+    // Bnnn acts as Bxnn and jumps to xnn + Vx instead of nnn + V0
+    pub jumping: bool,
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Self {
+            vf_reset: false,
+            memory: false,
+            display_wait: false,
+            clipping: false,
+            shifting: true,
+            jumping: false,
+        }
+    }
+}
+
+impl Quirks {
+    // COSMAC VIP
+    pub fn chip8() -> Self {
+        Self {
+            vf_reset: true,
+            memory: true,
+            display_wait: true,
+            clipping: true,
+            shifting: false,
+            jumping: false,
+        }
+    }
+
+    // SUPER-CHIP 1.1 on the HP48
+    pub fn superchip() -> Self {
+        Self {
+            vf_reset: false,
+            memory: false,
+            display_wait: false,
+            clipping: true,
+            shifting: true,
+            jumping: true,
+        }
+    }
+
+    // Octo
+    pub fn xochip() -> Self {
+        Self {
+            vf_reset: false,
+            memory: true,
+            display_wait: false,
+            clipping: false,
+            shifting: false,
+            jumping: false,
+        }
+    }
+
+    pub fn for_platform(platform: Platform) -> Self {
+        match platform {
+            Platform::Chip8 => Self::chip8(),
+            Platform::SuperChip => Self::superchip(),
+            Platform::XoChip => Self::xochip(),
+        }
+    }
+}
+
+// A preset (default, chip8, superchip, xochip) optionally followed by quirks to turn
+// on or off, e.g. "superchip,-clipping" or "default,+vf_reset,+memory".
+impl FromStr for Quirks {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(',').map(str::trim);
+        let mut quirks = match parts.next().unwrap_or_default() {
+            "default" | "" => Self::default(),
+            "chip8" => Self::chip8(),
+            "superchip" | "schip" => Self::superchip(),
+            "xochip" => Self::xochip(),
+            preset => return Err(format!("unknown quirks preset '{}'", preset)),
+        };
+        for part in parts {
+            let (on, name) = match part.strip_prefix('-') {
+                Some(name) => (false, name),
+                None => (true, part.strip_prefix('+').unwrap_or(part)),
+            };
+            match name {
+                "vf_reset" => quirks.vf_reset = on,
+                "memory" => quirks.memory = on,
+                "display_wait" => quirks.display_wait = on,
+                "clipping" => quirks.clipping = on,
+                "shifting" => quirks.shifting = on,
+                "jumping" => quirks.jumping = on,
+                _ => return Err(format!("unknown quirk '{}'", name)),
+            }
+        }
+        Ok(quirks)
+    }
+}
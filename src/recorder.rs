@@ -0,0 +1,251 @@
+use crate::machine::Machine;
+use crate::palette::Palette;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+pub const RECORDER_FPS: u32 = 60;
+// Five minutes, about 37 MB of 64x32 frames (four times that in high resolution)
+pub const RECORDER_MAX_FRAMES: usize = 5 * 60 * RECORDER_FPS as usize;
+pub const RECORDER_SAMPLE_RATE: u32 = 44100;
+pub const RECORDER_BUZZER_HZ: u32 = 440;
+
+// Shortest GIF frame, in hundredths of a second. Browsers show frames of 1 or 0 as about
+// 10, so faster animation would play several times too slowly.
+const GIF_MIN_DELAY: usize = 2;
+
+#[derive(PartialEq, Eq)]
+struct Frame {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+// Captures the framebuffer and buzzer state once per 60 Hz display frame.
+// The frontend (or a headless loop) decides where the frame boundary is and calls
+// `capture_frame` there; nothing here depends on a window or an audio device.
+pub struct Recorder {
+    frames: Vec<Frame>,
+    buzzer: Vec<bool>,
+    // Colors of the GIF
+    pub palette: Palette,
+    // Frames are kept in memory until saved, so capturing stops here
+    pub max_frames: usize,
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Self {
+            frames: Vec::new(),
+            buzzer: Vec::new(),
+            palette: Palette::default(),
+            max_frames: RECORDER_MAX_FRAMES,
+        }
+    }
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_palette(palette: Palette) -> Self {
+        Self {
+            palette,
+            ..Self::default()
+        }
+    }
+
+    // Does nothing once `max_frames` are captured
+    pub fn capture_frame(&mut self, machine: &impl Machine) {
+        if self.is_full() {
+            return;
+        }
+        let screen = machine.framebuffer();
+        self.frames.push(Frame {
+            width: screen.width,
+            height: screen.height,
+            pixels: screen.pixels.to_vec(),
+        });
+        self.buzzer.push(machine.sound());
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.frames.len() >= self.max_frames
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.buzzer.clear();
+    }
+
+    // Animated, endlessly looping GIF, each CHIP-8 pixel drawn as a `scale` x `scale` block.
+    // GIF delays are in hundredths of a second and no shorter than `GIF_MIN_DELAY`, so
+    // 60 Hz can't be kept: frames that would be shown for less are dropped, and delays
+    // are rounded against the running total (2, 3, 2, 3, ...) to keep the clip in sync.
+    // Runs of identical frames are merged into one frame with the summed delay. The GIF
+    // is as big as the largest frame, smaller ones are drawn top left.
+    pub fn write_gif<W: Write>(&self, w: W, scale: u16) -> io::Result<()> {
+        let scale = scale.max(1);
+        let size = |side: fn(&Frame) -> usize| {
+            self.frames.iter().map(side).max().unwrap_or(0) as u16 * scale
+        };
+        let width = size(|frame| frame.width);
+        let height = size(|frame| frame.height);
+        let palette = [self.palette.background, self.palette.foreground].concat();
+        let mut encoder = gif::Encoder::new(w, width, height, &palette).map_err(gif_err)?;
+        encoder.set_repeat(gif::Repeat::Infinite).map_err(gif_err)?;
+
+        let mut i = 0;
+        while i < self.frames.len() {
+            let mut end = i + 1;
+            while end < self.frames.len()
+                && (self.frames[end] == self.frames[i]
+                    || centis_at(end) - centis_at(i) < GIF_MIN_DELAY)
+            {
+                end += 1;
+            }
+            let delay = (centis_at(end) - centis_at(i)).max(GIF_MIN_DELAY);
+
+            let current = &self.frames[i];
+            let width = current.width as u16 * scale;
+            let height = current.height as u16 * scale;
+            let mut pixels = Vec::with_capacity(width as usize * height as usize);
+            for row in current.pixels.chunks(current.width) {
+                let line: Vec<u8> = row
+                    .iter()
+                    .flat_map(|px| std::iter::repeat_n(u8::from(*px != 0), scale as usize))
+                    .collect();
+                for _ in 0..scale {
+                    pixels.extend_from_slice(&line);
+                }
+            }
+            let mut frame = gif::Frame::from_indexed_pixels(width, height, pixels, None);
+            frame.dispose = gif::DisposalMethod::Background;
+            frame.delay = delay.min(u16::MAX as usize) as u16;
+            encoder.write_frame(&frame).map_err(gif_err)?;
+
+            i = end;
+        }
+        Ok(())
+    }
+
+    // Width and height of the raw frames: the largest resolution captured
+    pub fn raw_size(&self) -> (usize, usize) {
+        let width = self.frames.iter().map(|f| f.width).max().unwrap_or(0);
+        let height = self.frames.iter().map(|f| f.height).max().unwrap_or(0);
+        (width, height)
+    }
+
+    // Raw 8-bit grayscale frames (0 or 255 per pixel) of `raw_size`, 64x32 for CHIP-8,
+    // back to back at 60 fps, e.g.
+    // `ffmpeg -f rawvideo -pix_fmt gray -s 64x32 -r 60 -i rec.raw -i rec.wav rec.mp4`
+    // Frames at a lower resolution (before a switch to high resolution) are scaled up,
+    // so every frame in the stream has the same size.
+    pub fn write_raw_frames<W: Write>(&self, mut w: W) -> io::Result<()> {
+        let (width, height) = self.raw_size();
+        let mut pixels = Vec::with_capacity(width * height);
+        for frame in self.frames.iter() {
+            pixels.clear();
+            for y in 0..height {
+                let row = y * frame.height / height * frame.width;
+                pixels.extend((0..width).map(|x| {
+                    if frame.pixels[row + x * frame.width / width] == 0 {
+                        0
+                    } else {
+                        0xFF
+                    }
+                }));
+            }
+            w.write_all(&pixels)?;
+        }
+        w.flush()
+    }
+
+    // 16-bit mono PCM WAV of the buzzer: a square wave while the sound timer was running,
+    // silence otherwise, one 60th of a second per captured frame.
+    pub fn write_wav<W: Write>(&self, mut w: W) -> io::Result<()> {
+        let total_samples = samples_at(self.buzzer.len());
+        let data_len = total_samples as u32 * 2;
+
+        w.write_all(b"RIFF")?;
+        w.write_all(&(36 + data_len).to_le_bytes())?;
+        w.write_all(b"WAVE")?;
+        w.write_all(b"fmt ")?;
+        w.write_all(&16u32.to_le_bytes())?;
+        w.write_all(&1u16.to_le_bytes())?; // PCM
+        w.write_all(&1u16.to_le_bytes())?; // mono
+        w.write_all(&RECORDER_SAMPLE_RATE.to_le_bytes())?;
+        w.write_all(&(RECORDER_SAMPLE_RATE * 2).to_le_bytes())?;
+        w.write_all(&2u16.to_le_bytes())?;
+        w.write_all(&16u16.to_le_bytes())?;
+        w.write_all(b"data")?;
+        w.write_all(&data_len.to_le_bytes())?;
+
+        let half_period = (RECORDER_SAMPLE_RATE / RECORDER_BUZZER_HZ / 2) as usize;
+        let mut samples = Vec::with_capacity(data_len as usize);
+        for (frame, on) in self.buzzer.iter().enumerate() {
+            for s in samples_at(frame)..samples_at(frame + 1) {
+                let level: i16 = match (on, (s / half_period) % 2) {
+                    (false, _) => 0,
+                    (true, 0) => i16::MAX / 4,
+                    (true, _) => -(i16::MAX / 4),
+                };
+                samples.extend_from_slice(&level.to_le_bytes());
+            }
+        }
+        w.write_all(&samples)?;
+        w.flush()
+    }
+
+    pub fn save_gif(&self, path: impl AsRef<Path>, scale: u16) -> io::Result<()> {
+        self.write_gif(BufWriter::new(File::create(path)?), scale)
+    }
+
+    // Writes `<stem>.gif`, and with `with_raw` also `<stem>.raw` and `<stem>.wav` for muxing.
+    // The extension is appended, so dots in the stem (`pong.v2_1700000000`) are kept.
+    pub fn save(&self, stem: impl AsRef<Path>, scale: u16, with_raw: bool) -> io::Result<()> {
+        let stem = stem.as_ref();
+        self.save_gif(append_extension(stem, "gif"), scale)?;
+        if with_raw {
+            let raw = File::create(append_extension(stem, "raw"))?;
+            self.write_raw_frames(BufWriter::new(raw))?;
+            let wav = File::create(append_extension(stem, "wav"))?;
+            self.write_wav(BufWriter::new(wav))?;
+        }
+        Ok(())
+    }
+}
+
+fn append_extension(stem: &Path, extension: &str) -> PathBuf {
+    let mut path = OsString::from(stem);
+    path.push(".");
+    path.push(extension);
+    path.into()
+}
+
+// Elapsed time, in GIF centiseconds, at the start of frame n.
+fn centis_at(n: usize) -> usize {
+    (n * 100 + RECORDER_FPS as usize / 2) / RECORDER_FPS as usize
+}
+
+// Elapsed time, in audio samples, at the start of frame n.
+fn samples_at(n: usize) -> usize {
+    n * RECORDER_SAMPLE_RATE as usize / RECORDER_FPS as usize
+}
+
+fn gif_err(e: gif::EncodingError) -> io::Error {
+    match e {
+        gif::EncodingError::Io(e) => e,
+        e => io::Error::new(io::ErrorKind::InvalidData, e),
+    }
+}
@@ -0,0 +1,157 @@
+use crate::chip8::CHIP8_RAM;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+// Whether a rom fits depends on where it's loaded, see `Chip8::load`
+pub const CHIP8_MAX_ROM_SIZE: usize = CHIP8_RAM;
+
+#[derive(Debug)]
+pub enum RomError {
+    Io(io::Error),
+    TooLarge { size: usize, max: usize },
+    Empty,
+}
+
+impl fmt::Display for RomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RomError::Io(e) => write!(f, "could not read rom: {}", e),
+            RomError::TooLarge { size, max } => write!(
+                f,
+                "rom is {} bytes but only {} bytes of ram are available for it",
+                size, max
+            ),
+            RomError::Empty => write!(f, "rom is empty"),
+        }
+    }
+}
+
+impl std::error::Error for RomError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RomError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for RomError {
+    fn from(e: io::Error) -> Self {
+        RomError::Io(e)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Chip8,
+    SuperChip,
+    XoChip,
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Platform::Chip8 => "CHIP-8",
+            Platform::SuperChip => "SCHIP",
+            Platform::XoChip => "XO-CHIP",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rom {
+    bytes: Vec<u8>,
+    sha1: [u8; 20],
+    crc32: u32,
+}
+
+impl Rom {
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, RomError> {
+        Self::from_bytes(std::fs::read(path)?)
+    }
+
+    pub fn from_bytes(bytes: impl Into<Vec<u8>>) -> Result<Self, RomError> {
+        let bytes = bytes.into();
+        if bytes.is_empty() {
+            return Err(RomError::Empty);
+        }
+        if bytes.len() > CHIP8_MAX_ROM_SIZE {
+            return Err(RomError::TooLarge {
+                size: bytes.len(),
+                max: CHIP8_MAX_ROM_SIZE,
+            });
+        }
+        Ok(Self {
+            sha1: sha1_smol::Sha1::from(&bytes).digest().bytes(),
+            crc32: crc32fast::hash(&bytes),
+            bytes,
+        })
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn size(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn sha1(&self) -> [u8; 20] {
+        self.sha1
+    }
+
+    pub fn sha1_hex(&self) -> String {
+        self.sha1.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    pub fn crc32(&self) -> u32 {
+        self.crc32
+    }
+
+    // Best guess at the platform the rom was written for, from the opcodes it contains.
+    // Code and data aren't told apart, so this is only a hint: sprite data can look like
+    // an extended opcode, and a rom that never uses its platform's extensions reads as CHIP-8.
+    pub fn platform_hint(&self) -> Platform {
+        let mut platform = Platform::Chip8;
+        for opcode in self.words() {
+            match opcode_platform(opcode) {
+                Platform::XoChip => return Platform::XoChip,
+                Platform::SuperChip => platform = Platform::SuperChip,
+                Platform::Chip8 => (),
+            }
+        }
+        platform
+    }
+
+    // 0nnn (other than 00E0/00EE) calls native COSMAC VIP machine code, which only
+    // "hybrid" roms do and which this interpreter can't run.
+    pub fn calls_machine_code(&self) -> bool {
+        self.words().any(|opcode| {
+            opcode & 0xF000 == 0
+                && !matches!(opcode, 0x0000 | 0x00E0 | 0x00EE)
+                && opcode_platform(opcode) == Platform::Chip8
+        })
+    }
+
+    fn words(&self) -> impl Iterator<Item = u16> + '_ {
+        self.bytes
+            .chunks_exact(2)
+            .map(|w| (w[0] as u16) << 8 | w[1] as u16)
+    }
+}
+
+fn opcode_platform(opcode: u16) -> Platform {
+    match ((opcode & 0xF000) >> 12, opcode & 0x000F, opcode & 0x00FF) {
+        // F000 nnnn, Fn01, F002, Fx3A, 5xy2, 5xy3, 00Dn
+        (0xF, _, 0x00) | (0xF, _, 0x01) | (0xF, _, 0x02) | (0xF, _, 0x3A) => Platform::XoChip,
+        (0x5, 0x2, _) | (0x5, 0x3, _) => Platform::XoChip,
+        (0x0, _, kk) if kk & 0xF0 == 0xD0 && opcode & 0x0F00 == 0 => Platform::XoChip,
+        // 00Cn, 00FB-00FF, Fx30, Fx75, Fx85
+        (0x0, _, kk) if opcode & 0x0F00 == 0 && (kk & 0xF0 == 0xC0 || kk >= 0xFB) => {
+            Platform::SuperChip
+        }
+        (0xF, _, 0x30) | (0xF, _, 0x75) | (0xF, _, 0x85) => Platform::SuperChip,
+        _ => Platform::Chip8,
+    }
+}
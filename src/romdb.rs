@@ -0,0 +1,230 @@
+use crate::quirks::Quirks;
+use crate::rom::{Platform, Rom};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::sync::OnceLock;
+
+// Programs file in the format of https://github.com/chip-8/chip-8-database
+// (`database/programs.json`), which can be dropped in as-is. A program is only
+// recognised through the SHA-1 keys of its `roms` map.
+const BUNDLED_DATABASE: &str = include_str!("romdb.json");
+
+#[derive(Debug)]
+pub enum RomDatabaseError {
+    Io(io::Error),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for RomDatabaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RomDatabaseError::Io(e) => write!(f, "could not read rom database: {}", e),
+            RomDatabaseError::Json(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for RomDatabaseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RomDatabaseError::Io(e) => Some(e),
+            RomDatabaseError::Json(e) => Some(e),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RomInfo {
+    pub title: String,
+    pub description: Option<String>,
+    pub release: Option<String>,
+    pub authors: Vec<String>,
+    pub file: Option<String>,
+    pub platform: Platform,
+    pub quirks: Quirks,
+    // Instructions per 60 Hz frame
+    pub tick_rate: Option<usize>,
+    // Named controls ("up", "a", ...) to CHIP-8 keys
+    pub keys: BTreeMap<String, u8>,
+    pub colors: Option<RomColors>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RomColors {
+    // Background first, then foreground (further entries are for extra XO-CHIP planes)
+    pub pixels: Vec<[u8; 3]>,
+    pub buzzer: Option<[u8; 3]>,
+    pub silence: Option<[u8; 3]>,
+}
+
+#[derive(Debug, Default)]
+pub struct RomDatabase {
+    by_sha1: HashMap<String, RomInfo>,
+}
+
+impl RomDatabase {
+    pub fn bundled() -> &'static RomDatabase {
+        static DATABASE: OnceLock<RomDatabase> = OnceLock::new();
+        DATABASE.get_or_init(|| {
+            RomDatabase::from_json(BUNDLED_DATABASE).expect("bundled rom database is valid")
+        })
+    }
+
+    // A programs.json, e.g. the full one from chip-8-database
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, RomDatabaseError> {
+        let json = std::fs::read_to_string(path).map_err(RomDatabaseError::Io)?;
+        Self::from_json(&json).map_err(RomDatabaseError::Json)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        let mut db = Self::default();
+        db.merge_json(json)?;
+        Ok(db)
+    }
+
+    // Entries from `json` replace existing entries with the same hash.
+    pub fn merge_json(&mut self, json: &str) -> Result<(), serde_json::Error> {
+        let programs: Vec<Program> = serde_json::from_str(json)?;
+        for program in programs {
+            for (sha1, entry) in program.roms.iter() {
+                if let Some(info) = program.rom_info(entry) {
+                    self.by_sha1.insert(sha1.to_ascii_lowercase(), info);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn lookup(&self, rom: &Rom) -> Option<&RomInfo> {
+        self.lookup_sha1(&rom.sha1_hex())
+    }
+
+    pub fn lookup_sha1(&self, sha1: &str) -> Option<&RomInfo> {
+        self.by_sha1.get(&sha1.to_ascii_lowercase())
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_sha1.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_sha1.is_empty()
+    }
+}
+
+// The programs of a programs.json that list any of `roms`, each with only those roms,
+// for building a smaller database like the bundled one (see examples/romdb_import.rs).
+// Everything else about the programs is kept as it is.
+pub fn select_programs(
+    json: &str,
+    roms: &[Rom],
+) -> Result<Vec<serde_json::Value>, serde_json::Error> {
+    let wanted: HashSet<String> = roms.iter().map(Rom::sha1_hex).collect();
+    let programs: Vec<serde_json::Value> = serde_json::from_str(json)?;
+    Ok(programs
+        .into_iter()
+        .filter_map(|mut program| {
+            let roms = program.get_mut("roms")?.as_object_mut()?;
+            roms.retain(|sha1, _| wanted.contains(&sha1.to_ascii_lowercase()));
+            (!roms.is_empty()).then_some(program)
+        })
+        .collect())
+}
+
+#[derive(Deserialize)]
+struct Program {
+    title: String,
+    description: Option<String>,
+    release: Option<String>,
+    #[serde(default)]
+    authors: Vec<String>,
+    #[serde(default)]
+    roms: BTreeMap<String, RomEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RomEntry {
+    file: Option<String>,
+    #[serde(default)]
+    platforms: Vec<String>,
+    #[serde(default)]
+    quirky_platforms: HashMap<String, HashMap<String, bool>>,
+    tickrate: Option<usize>,
+    #[serde(default)]
+    keys: BTreeMap<String, u8>,
+    colors: Option<ColorsEntry>,
+}
+
+#[derive(Deserialize)]
+struct ColorsEntry {
+    #[serde(default)]
+    pixels: Vec<String>,
+    buzzer: Option<String>,
+    silence: Option<String>,
+}
+
+impl Program {
+    // The first listed platform this emulator knows how to run picks the quirks,
+    // then any per-platform overrides from `quirkyPlatforms` are applied on top.
+    fn rom_info(&self, entry: &RomEntry) -> Option<RomInfo> {
+        let (id, platform, mut quirks) = entry
+            .platforms
+            .iter()
+            .find_map(|id| platform_quirks(id).map(|(p, q)| (id, p, q)))?;
+        if let Some(overrides) = entry.quirky_platforms.get(id) {
+            for (quirk, value) in overrides.iter() {
+                match quirk.as_str() {
+                    "shift" => quirks.shifting = *value,
+                    "memoryLeaveIUnchanged" => quirks.memory = !*value,
+                    "memoryIncrementByX" => quirks.memory = quirks.memory || *value,
+                    "wrap" => quirks.clipping = !*value,
+                    "jump" => quirks.jumping = *value,
+                    "vblank" => quirks.display_wait = *value,
+                    "logic" => quirks.vf_reset = *value,
+                    _ => (),
+                }
+            }
+        }
+
+        Some(RomInfo {
+            title: self.title.clone(),
+            description: self.description.clone(),
+            release: self.release.clone(),
+            authors: self.authors.clone(),
+            file: entry.file.clone(),
+            platform,
+            quirks,
+            tick_rate: entry.tickrate,
+            keys: entry.keys.clone(),
+            colors: entry.colors.as_ref().map(|c| RomColors {
+                pixels: c.pixels.iter().filter_map(|p| parse_color(p)).collect(),
+                buzzer: c.buzzer.as_deref().and_then(parse_color),
+                silence: c.silence.as_deref().and_then(parse_color),
+            }),
+        })
+    }
+}
+
+fn platform_quirks(id: &str) -> Option<(Platform, Quirks)> {
+    match id {
+        "originalChip8" | "hybridVIP" => Some((Platform::Chip8, Quirks::chip8())),
+        "modernChip8" => Some((Platform::Chip8, Quirks::default())),
+        "chip48" | "superchip1" | "superchip" => Some((Platform::SuperChip, Quirks::superchip())),
+        "xochip" => Some((Platform::XoChip, Quirks::xochip())),
+        _ => None,
+    }
+}
+
+// "#rrggbb" or "rrggbb"
+fn parse_color(s: &str) -> Option<[u8; 3]> {
+    let s = s.strip_prefix('#').unwrap_or(s);
+    if s.len() != 6 {
+        return None;
+    }
+    let v = u32::from_str_radix(s, 16).ok()?;
+    Some([(v >> 16) as u8, (v >> 8) as u8, v as u8])
+}
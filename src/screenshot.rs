@@ -0,0 +1,79 @@
+use crate::machine::Framebuffer;
+use crate::palette::Palette;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+// RGB PNG of the screen, each CHIP-8 pixel drawn as a `scale` x `scale` block.
+pub fn write_png<W: Write>(
+    w: W,
+    screen: &Framebuffer,
+    palette: &Palette,
+    scale: u32,
+) -> io::Result<()> {
+    let scale = scale.max(1) as usize;
+    let width = screen.width * scale;
+    let height = screen.height * scale;
+    let mut encoder = png::Encoder::new(w, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(png_err)?;
+
+    let mut data = Vec::with_capacity(width * height * 3);
+    for row in screen.rows() {
+        let line: Vec<u8> = row
+            .iter()
+            .flat_map(|px| std::iter::repeat_n(palette.color(*px), scale))
+            .flatten()
+            .collect();
+        for _ in 0..scale {
+            data.extend_from_slice(&line);
+        }
+    }
+    writer.write_image_data(&data).map_err(png_err)?;
+    writer.finish().map_err(png_err)
+}
+
+pub fn save_png(
+    path: impl AsRef<Path>,
+    screen: &Framebuffer,
+    palette: &Palette,
+    scale: u32,
+) -> io::Result<()> {
+    write_png(BufWriter::new(File::create(path)?), screen, palette, scale)
+}
+
+// Plain (P1) PBM, one digit per CHIP-8 pixel with 1 for lit. Rows wider than the
+// format's 70 character line limit go on several lines, which readers don't mind.
+pub fn write_pbm<W: Write>(mut w: W, screen: &Framebuffer) -> io::Result<()> {
+    writeln!(w, "P1\n{} {}", screen.width, screen.height)?;
+    for row in screen.rows() {
+        for part in row.chunks(70) {
+            let line: String = part
+                .iter()
+                .map(|px| if *px != 0 { '1' } else { '0' })
+                .collect();
+            writeln!(w, "{}", line)?;
+        }
+    }
+    Ok(())
+}
+
+// One character per pixel, '#' for lit and '.' for dark, for logs and diffs.
+pub fn write_ascii<W: Write>(mut w: W, screen: &Framebuffer) -> io::Result<()> {
+    for row in screen.rows() {
+        let line: String = row
+            .iter()
+            .map(|px| if *px != 0 { '#' } else { '.' })
+            .collect();
+        writeln!(w, "{}", line)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn png_err(e: png::EncodingError) -> io::Error {
+    match e {
+        png::EncodingError::IoError(e) => e,
+        e => io::Error::new(io::ErrorKind::InvalidData, e),
+    }
+}
@@ -0,0 +1,134 @@
+use crate::chip8::{
+    Chip8, CHIP8_MAX_STACK_DEPTH, CHIP8_RAM, CHIP8_SCREEN_HEIGHT, CHIP8_SCREEN_WIDTH,
+};
+use std::fmt;
+
+const STATE_MAGIC: &[u8; 4] = b"C8ST";
+// 2 stores how many stack slots follow, 1 always had 16
+const STATE_VERSION: u8 = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateError {
+    BadMagic,
+    UnsupportedVersion(u8),
+    Truncated,
+    // A register in the state points outside the machine (pc or sp). I may: Fx1E can
+    // take it anywhere in its 16 bits.
+    Invalid(&'static str),
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::BadMagic => write!(f, "not a chip8 save state"),
+            StateError::UnsupportedVersion(v) => write!(f, "unsupported save state version {}", v),
+            StateError::Truncated => write!(f, "save state is truncated"),
+            StateError::Invalid(what) => write!(f, "save state has an invalid {}", what),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+// Save states hold what the running program can observe or change: ram, registers,
+// stack (with ram, if the profile keeps it there), timers, screen, held keys and the
+// random number generator. Configuration (quirks, profile, tick rate) is left alone
+// when a state is loaded.
+impl Chip8 {
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut out =
+            Vec::with_capacity(CHIP8_RAM + CHIP8_SCREEN_WIDTH * CHIP8_SCREEN_HEIGHT + 128);
+        out.extend_from_slice(STATE_MAGIC);
+        out.push(STATE_VERSION);
+        out.extend_from_slice(self.ram());
+        out.extend_from_slice(&self.v);
+        out.extend_from_slice(&(self.i as u16).to_le_bytes());
+        out.extend_from_slice(&(self.pc as u16).to_le_bytes());
+        out.push(self.sp as u8);
+        out.push(self.stack.len() as u8);
+        self.stack
+            .iter()
+            .for_each(|addr| out.extend_from_slice(&(*addr as u16).to_le_bytes()));
+        self.screen
+            .iter()
+            .for_each(|row| out.extend_from_slice(row));
+        out.push(self.delay_timer);
+        out.push(self.sound_timer);
+        self.keypad.iter().for_each(|key| out.push(*key as u8));
+        out.extend_from_slice(&self.rng_state().to_le_bytes());
+        out
+    }
+
+    // The machine is only changed if the whole state could be read.
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        let mut r = Reader(state);
+        if r.take(4)? != STATE_MAGIC {
+            return Err(StateError::BadMagic);
+        }
+        let version = r.u8()?;
+        if !(1..=STATE_VERSION).contains(&version) {
+            return Err(StateError::UnsupportedVersion(version));
+        }
+
+        let mut next = *self;
+        next.write_ram(0, r.take(CHIP8_RAM)?);
+        next.v.copy_from_slice(r.take(16)?);
+        next.i = r.u16()? as usize;
+        next.pc = r.u16()? as usize;
+        next.sp = r.u8()? as usize;
+        let slots = if version == 1 { 16 } else { r.u8()? as usize };
+        if slots > next.stack.len() {
+            return Err(StateError::Invalid("stack size"));
+        }
+        next.stack = [0; CHIP8_MAX_STACK_DEPTH];
+        for addr in next.stack[..slots].iter_mut() {
+            *addr = r.u16()? as usize;
+        }
+        for row in next.screen.iter_mut() {
+            row.copy_from_slice(r.take(CHIP8_SCREEN_WIDTH)?);
+        }
+        next.delay_timer = r.u8()?;
+        next.sound_timer = r.u8()?;
+        for key in next.keypad.iter_mut() {
+            *key = r.u8()? != 0;
+        }
+        let rng = u64::from_le_bytes(r.take(8)?.try_into().unwrap());
+
+        if next.pc > CHIP8_RAM - 2 {
+            return Err(StateError::Invalid("pc"));
+        }
+        if next.sp > next.stack_depth() {
+            return Err(StateError::Invalid("stack pointer"));
+        }
+        if rng == 0 {
+            return Err(StateError::Invalid("random state"));
+        }
+        next.set_rng_state(rng);
+        next.pressed = [false; 16];
+        next.released = [false; 16];
+        next.cancel_key_wait();
+        *self = next;
+        Ok(())
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], StateError> {
+        if self.0.len() < n {
+            return Err(StateError::Truncated);
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, StateError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, StateError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+}
@@ -0,0 +1,108 @@
+use crate::chip8::{Chip8, CHIP8_RAM};
+use std::fmt;
+use std::str::FromStr;
+
+// The VIP's 1802 runs at 1.7609 MHz and takes 8 clocks per machine cycle, so a 60 Hz
+// frame lasts about 3668 machine cycles. The video chip borrows 1024 of them for
+// display DMA (128 lines of 8 bytes) and the interrupt routine around it takes some
+// more, which leaves the interpreter the rest.
+pub const VIP_FRAME_CYCLES: i64 = 3668;
+pub const VIP_DISPLAY_CYCLES: i64 = 1024 + 46;
+
+// Fetching and decoding an instruction, on top of what it costs to carry it out
+const FETCH_CYCLES: u32 = 40;
+
+// How `Chip8::frame` decides how much to run in a 60 Hz frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Timing {
+    // `tick_rate` instructions, all of them equally fast
+    #[default]
+    Instructions,
+    // As many instructions as fit in the machine cycles the COSMAC VIP interpreter had
+    // per frame, each costing what it cost there (see `vip_cycles`). Dxyn waits for the
+    // display interrupt, i.e. the frame ends after a sprite is drawn. `tick_rate` is
+    // ignored.
+    Vip,
+}
+
+impl Timing {
+    pub const ALL: [Timing; 2] = [Timing::Instructions, Timing::Vip];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Timing::Instructions => "instructions",
+            Timing::Vip => "vip",
+        }
+    }
+}
+
+impl fmt::Display for Timing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Timing {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Timing::ALL
+            .into_iter()
+            .find(|timing| timing.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown timing '{}'", s))
+    }
+}
+
+// Machine cycles the VIP interpreter spends on the instruction `chip8` is about to run,
+// worked out from the state before it runs. The costs follow the interpreter's routines
+// closely but not to the cycle:
+//
+// - skips cost 8 more when they skip
+// - 00E0 clears the 256 bytes of display memory one at a time
+// - Dxyn shifts every sprite row into place one bit at a time, so sprites that aren't
+//   byte aligned cost more, and rows that straddle two display bytes cost more again
+// - Fx33 divides by repeated subtraction, one round per unit of each digit
+// - Fx55 and Fx65 copy one register at a time
+pub fn vip_cycles(chip8: &Chip8) -> u32 {
+    if chip8.pc + 1 >= CHIP8_RAM {
+        return FETCH_CYCLES;
+    }
+    let opcode = chip8.get_opcode();
+    let (i, x, y, n, kk, _) = Chip8::inst_decode(&opcode);
+    let vx = chip8.v[x as usize];
+    let vy = chip8.v[y as usize];
+    let skip = |taken: bool| if taken { 8 } else { 0 };
+    let cycles = match (i, n, opcode & 0xFF) {
+        (0x0, _, 0xE0) => 24 + 256 * 6,
+        (0x0, _, 0xEE) => 10,
+        (0x0, _, _) => 0,
+        (0x1, _, _) => 12,
+        (0x2, _, _) => 26,
+        (0x3, _, _) => 10 + skip(vx == kk),
+        (0x4, _, _) => 10 + skip(vx != kk),
+        (0x5, _, _) => 18 + skip(vx == vy),
+        (0x6, _, _) => 6,
+        (0x7, _, _) => 10,
+        (0x8, _, _) => 44,
+        (0x9, _, _) => 18 + skip(vx != vy),
+        (0xA, _, _) => 12,
+        (0xB, _, _) => 26,
+        (0xC, _, _) => 36,
+        (0xD, _, _) => {
+            let shift = (vx % 8) as u32;
+            let straddle = if shift == 0 { 0 } else { 16 };
+            46 + n as u32 * (34 + 4 * shift + straddle)
+        }
+        (0xE, _, 0x9E) => 18 + skip(chip8.keypad[(vx & 0xF) as usize]),
+        (0xE, _, 0xA1) => 18 + skip(!chip8.keypad[(vx & 0xF) as usize]),
+        (0xF, _, 0x33) => {
+            let units = (vx / 100 + vx / 10 % 10 + vx % 10) as u32;
+            44 + 16 * units
+        }
+        (0xF, _, 0x55) | (0xF, _, 0x65) => 18 + 14 * (x as u32 + 1),
+        (0xF, _, 0x1E) | (0xF, _, 0x29) => 16,
+        (0xF, _, _) => 6,
+        _ => 0,
+    };
+    FETCH_CYCLES + cycles
+}
@@ -0,0 +1,107 @@
+use chip8_rs::analysis::{Analysis, Problem, Severity};
+use chip8_rs::rom::Rom;
+
+fn analyse(bytes: &[u8]) -> Analysis {
+    Analysis::of(&Rom::from_bytes(bytes.to_vec()).unwrap())
+}
+
+fn problems(analysis: &Analysis) -> Vec<(usize, &Problem)> {
+    analysis
+        .diagnostics
+        .iter()
+        .map(|d| (d.addr, &d.problem))
+        .collect()
+}
+
+#[test]
+fn code_is_followed_and_data_left_alone() {
+    // LD V0, 1; CALL 0x208; JP 0x204; <data>; RET
+    let analysis = analyse(&[0x60, 0x01, 0x22, 0x08, 0x12, 0x04, 0xFF, 0xFF, 0x00, 0xEE]);
+    assert!(analysis.diagnostics.is_empty());
+    assert_eq!(
+        analysis.reachable.iter().copied().collect::<Vec<_>>(),
+        vec![0x200, 0x202, 0x204, 0x208]
+    );
+    assert!(analysis.subroutines.contains(&0x208));
+}
+
+#[test]
+fn unknown_opcodes_are_errors() {
+    // <unknown>; JP 0x202
+    let analysis = analyse(&[0xFF, 0xFF, 0x12, 0x02]);
+    assert!(analysis.has_errors());
+    assert_eq!(problems(&analysis), vec![(0x200, &Problem::UnknownOpcode)]);
+}
+
+#[test]
+fn jumps_and_returns_that_go_nowhere_are_reported() {
+    // SE V0, 0; JP 0x300; RET
+    let analysis = analyse(&[0x30, 0x00, 0x13, 0x00, 0x00, 0xEE]);
+    assert_eq!(
+        problems(&analysis),
+        vec![
+            (0x202, &Problem::JumpOutsideRom { target: 0x300 }),
+            (0x204, &Problem::ReturnOutsideSubroutine),
+        ]
+    );
+    assert_eq!(analysis.count(Severity::Warning), 1);
+    assert_eq!(analysis.count(Severity::Error), 1);
+}
+
+#[test]
+fn memory_past_the_end_of_ram_is_an_error() {
+    // LD I, 0xFFF; LD [I], V1; JP 0x204
+    let analysis = analyse(&[0xAF, 0xFF, 0xF1, 0x55, 0x12, 0x04]);
+    assert!(analysis.has_errors());
+    assert!(problems(&analysis).contains(&(0x202, &Problem::MemoryOutOfRange { i: 0xFFF, len: 2 })));
+}
+
+#[test]
+fn calling_itself_overflows_the_stack() {
+    // CALL 0x200
+    let analysis = analyse(&[0x22, 0x00]);
+    assert!(analysis.has_errors());
+    assert_eq!(analysis.max_call_depth, None);
+    assert!(problems(&analysis).contains(&(
+        0x200,
+        &Problem::StackOverflow {
+            depth: None,
+            limit: 16
+        }
+    )));
+}
+
+#[test]
+fn call_cycle_without_a_way_out_overflows_the_stack() {
+    // CALL 0x204; JP 0x202; CALL 0x208; RET; CALL 0x204; RET
+    let analysis = analyse(&[
+        0x22, 0x04, 0x12, 0x02, 0x22, 0x08, 0x00, 0xEE, 0x22, 0x04, 0x00, 0xEE,
+    ]);
+    assert!(analysis.has_errors());
+    assert_eq!(analysis.max_call_depth, None);
+    assert!(analysis
+        .diagnostics
+        .iter()
+        .any(|d| matches!(d.problem, Problem::StackOverflow { depth: None, .. })));
+}
+
+#[test]
+fn recursion_that_can_return_is_a_warning() {
+    // CALL 0x204; JP 0x202; SE V0, 0; CALL 0x204; RET
+    let analysis = analyse(&[0x22, 0x04, 0x12, 0x02, 0x30, 0x00, 0x22, 0x04, 0x00, 0xEE]);
+    assert!(!analysis.has_errors());
+    assert_eq!(analysis.max_call_depth, None);
+    assert_eq!(
+        problems(&analysis),
+        vec![(0x206, &Problem::Recursion { target: 0x204 })]
+    );
+    assert_eq!(analysis.count(Severity::Warning), 1);
+}
+
+#[test]
+fn nested_calls_are_counted() {
+    // CALL 0x204; JP 0x202; CALL 0x208; RET; RET
+    let analysis = analyse(&[0x22, 0x04, 0x12, 0x02, 0x22, 0x08, 0x00, 0xEE, 0x00, 0xEE]);
+    assert!(analysis.diagnostics.is_empty());
+    assert_eq!(analysis.max_call_depth, Some(2));
+}
@@ -0,0 +1,135 @@
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+// Draws the top row of a sprite at 0,0 and stops on a jump to itself
+const DRAWS_AND_HALTS: [u8; 7] = [
+    0xA2, 0x06, // 200: LD I, 0x206
+    0xD0, 0x11, // 202: DRW V0, V1, 1
+    0x12, 0x04, // 204: JP 0x204
+    0xF0, // 206: sprite
+];
+// ADD V0, 1; JP 0x200
+const LOOPS: [u8; 4] = [0x70, 0x01, 0x12, 0x00];
+// RET
+const ERRORS: [u8; 2] = [0x00, 0xEE];
+
+// A file in a directory of its own for `test`
+fn temp_path(test: &str, name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(test);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join(name)
+}
+
+fn run(test: &str, rom: &[u8], args: &[&str]) -> Output {
+    let path = temp_path(test, "rom.ch8");
+    std::fs::write(&path, rom).unwrap();
+    Command::new(env!("CARGO_BIN_EXE_chip8-run"))
+        .arg(&path)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn halting_exits_with_0() {
+    let output = run("halting", &DRAWS_AND_HALTS, &["--quiet"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "halted at 0x204 after 1 frames\n");
+}
+
+#[test]
+fn timing_out_exits_with_0_unless_a_halt_is_expected() {
+    let output = run("timing_out", &LOOPS, &["--quiet", "--frames", "5"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "timed out after 5 frames at 0x200\n");
+
+    let output = run(
+        "timing_out",
+        &LOOPS,
+        &["--quiet", "--frames", "5", "--expect-halt"],
+    );
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn emulator_errors_exit_with_1() {
+    let output = run("erroring", &ERRORS, &["--quiet"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).starts_with("errored on frame 0: "));
+}
+
+#[test]
+fn bad_arguments_exit_with_2() {
+    let output = run("bad_arguments", &LOOPS, &["--frames", "many"]);
+    assert_eq!(output.status.code(), Some(2));
+
+    let missing = temp_path("bad_arguments", "missing.ch8");
+    let output = Command::new(env!("CARGO_BIN_EXE_chip8-run"))
+        .arg(&missing)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("missing.ch8"));
+
+    let script = temp_path("bad_arguments", "input.txt");
+    std::fs::write(&script, "0 G\n").unwrap();
+    let output = run(
+        "bad_arguments",
+        &LOOPS,
+        &["--input", script.to_str().unwrap()],
+    );
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn prints_the_screen_and_registers() {
+    let output = run("printing", &DRAWS_AND_HALTS, &[]);
+    let stdout = stdout(&output);
+    let mut lines = stdout.lines();
+    assert_eq!(
+        lines.next(),
+        Some(format!("####{}", ".".repeat(60)).as_str())
+    );
+    assert_eq!(lines.next(), Some(".".repeat(64).as_str()));
+    assert!(stdout.contains("\nPC 0x204  I 0x206  SP 0  DT 0  ST 0\n"));
+    assert!(stdout.ends_with("halted at 0x204 after 1 frames\n"));
+}
+
+#[test]
+fn writes_the_screen_as_ascii_pbm_or_png() {
+    let ascii = temp_path("screens", "screen.txt");
+    let pbm = temp_path("screens", "screen.pbm");
+    let png = temp_path("screens", "screen.png");
+    for path in [&ascii, &pbm, &png] {
+        let output = run(
+            "screens",
+            &DRAWS_AND_HALTS,
+            &["--quiet", "--screen", path.to_str().unwrap()],
+        );
+        assert_eq!(output.status.code(), Some(0));
+    }
+
+    let ascii = std::fs::read_to_string(ascii).unwrap();
+    assert_eq!(ascii.lines().count(), 32);
+    assert!(ascii.starts_with(&format!("####{}\n", ".".repeat(60))));
+
+    let pbm = std::fs::read_to_string(pbm).unwrap();
+    assert!(pbm.starts_with(&format!("P1\n64 32\n1111{}\n", "0".repeat(60))));
+    assert_eq!(pbm.lines().count(), 2 + 32);
+
+    // 10 pixels per CHIP-8 pixel, white on black
+    let decoder = png::Decoder::new(std::io::BufReader::new(std::fs::File::open(png).unwrap()));
+    let mut reader = decoder.read_info().unwrap();
+    let mut data = vec![0; reader.output_buffer_size().unwrap()];
+    let info = reader.next_frame(&mut data).unwrap();
+    assert_eq!((info.width, info.height), (640, 320));
+    assert_eq!(info.color_type, png::ColorType::Rgb);
+    assert_eq!(data[..3], [0xFF; 3]);
+    assert_eq!(data[39 * 3..40 * 3], [0xFF; 3]);
+    assert_eq!(data[40 * 3..41 * 3], [0x00; 3]);
+    assert_eq!(data[640 * 3 * 10..640 * 3 * 10 + 3], [0x00; 3]);
+}
@@ -0,0 +1,107 @@
+use chip8_rs::chip8::Chip8;
+use chip8_rs::coverage::{Coverage, CoverageError, EXECUTED, READ, WRITTEN};
+use chip8_rs::profile::{Profile, StackLayout};
+use chip8_rs::rom::Rom;
+
+// Reads the byte at 0x20c only while key 0 is down
+const BRANCHES: [u8; 13] = [
+    0xE0, 0x9E, // 200: SKP V0
+    0x12, 0x0A, // 202: JP 0x20a
+    0xA2, 0x0C, // 204: LD I, 0x20c
+    0xF0, 0x65, // 206: LD V0, [I]
+    0x12, 0x08, // 208: JP 0x208
+    0x12, 0x0A, // 20A: JP 0x20a
+    0xF0, // 20C: data
+];
+
+fn cover(chip8: &mut Chip8, rom: &[u8]) -> Coverage {
+    chip8.load(&Rom::from_bytes(rom.to_vec()).unwrap()).unwrap();
+    chip8.tick_rate = 10;
+    let mut coverage = Coverage::new();
+    coverage.frame(chip8).unwrap();
+    coverage
+}
+
+#[test]
+fn merged_runs_cover_both_branches() {
+    let mut chip8 = Chip8::new();
+    let idle = cover(&mut chip8, &BRANCHES);
+    let mut chip8 = Chip8::new();
+    chip8.key_down(0);
+    let pressed = cover(&mut chip8, &BRANCHES);
+    assert!(!idle.is(0x204, EXECUTED));
+    assert!(!pressed.is(0x20A, EXECUTED));
+
+    let mut merged = Coverage::from_bitmap(idle.to_bitmap()).unwrap();
+    merged.merge(&pressed);
+    assert_eq!(merged.count(0x200..0x20C, EXECUTED), 12);
+    assert_eq!(merged.count(0x200..0x20D, READ), 1);
+    assert!(merged.is(0x20C, READ));
+    assert!(!merged.is(0x20C, EXECUTED));
+
+    let mut listing = Vec::new();
+    merged
+        .write_listing(&mut listing, &BRANCHES, 0x200)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(listing).unwrap(),
+        "executed 12 of 13 bytes (92.3%), read 1, written 0\n\
+         0x200  e09e  x--  SKP V0\n\
+         0x202  120a  x--  JP 0x20a\n\
+         0x204  a20c  x--  LD I, 0x20c\n\
+         0x206  f065  x--  LD V0, [I]\n\
+         0x208  1208  x--  JP 0x208\n\
+         0x20a  120a  x--  JP 0x20a\n\
+         0x20c  f0    -r-  DB 0xf0\n"
+    );
+}
+
+#[test]
+fn bitmaps_are_one_byte_per_address() {
+    let coverage = cover(&mut Chip8::new(), &BRANCHES);
+    assert_eq!(coverage.to_bitmap().len(), 4096);
+    assert_eq!(
+        Coverage::from_bitmap(coverage.to_bitmap()),
+        Ok(coverage.clone())
+    );
+    assert_eq!(
+        Coverage::from_bitmap(&[0; 100]),
+        Err(CoverageError::WrongSize { size: 100 })
+    );
+}
+
+#[test]
+fn bcd_and_register_stores_are_writes() {
+    let rom = [
+        0xA3, 0x00, // 200: LD I, 0x300
+        0xF0, 0x33, // 202: LD B, V0
+        0xA3, 0x10, // 204: LD I, 0x310
+        0xF1, 0x55, // 206: LD [I], V1
+        0x12, 0x08, // 208: JP 0x208
+    ];
+    let coverage = cover(&mut Chip8::new(), &rom);
+    assert_eq!(coverage.count(0x300..0x400, WRITTEN), 5);
+    assert!(coverage.is(0x302, WRITTEN));
+    assert!(coverage.is(0x311, WRITTEN));
+    assert_eq!(coverage.count(0x300..0x400, READ), 0);
+}
+
+#[test]
+fn calls_write_and_returns_read_a_stack_in_ram() {
+    let rom = [
+        0x22, 0x04, // 200: CALL 0x204
+        0x12, 0x02, // 202: JP 0x202
+        0x00, 0xEE, // 204: RET
+    ];
+    let mut chip8 =
+        Chip8::with_profile(Profile::default().with_stack(StackLayout::in_ram(12, 0xEA0)));
+    let coverage = cover(&mut chip8, &rom);
+    assert_eq!(coverage.count(0xEA0..0xEA2, WRITTEN), 2);
+    assert_eq!(coverage.count(0xEA0..0xEA2, READ), 2);
+    assert_eq!(coverage.count(0xEA2..0x1000, READ | WRITTEN), 0);
+
+    // Not with the stack outside ram
+    let coverage = cover(&mut Chip8::new(), &rom);
+    assert_eq!(coverage.count(0..0x200, READ | WRITTEN), 0);
+    assert_eq!(coverage.count(0x206..0x1000, READ | WRITTEN), 0);
+}
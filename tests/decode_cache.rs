@@ -0,0 +1,32 @@
+use chip8_rs::chip8::Chip8;
+use chip8_rs::rom::Rom;
+
+// LD V0, 1; JP 0x200
+const LOOP: [u8; 4] = [0x60, 0x01, 0x12, 0x00];
+
+#[test]
+fn patched_ram_runs_the_new_opcode() {
+    let mut chip8 = Chip8::new();
+    chip8.load(&Rom::from_bytes(LOOP).unwrap()).unwrap();
+    chip8.exec().unwrap();
+    chip8.exec().unwrap();
+    assert_eq!(chip8.v[0], 1);
+
+    // LD V0, 2 over the LD V0, 1 that ran, and so was decoded, already
+    chip8.write_ram(0x200, &[0x60, 0x02]);
+    chip8.exec().unwrap();
+    assert_eq!(chip8.v[0], 2);
+    assert_eq!(chip8.ram()[0x200..0x202], [0x60, 0x02]);
+}
+
+#[test]
+fn patching_the_second_byte_drops_the_instruction_before() {
+    let mut chip8 = Chip8::new();
+    chip8.load(&Rom::from_bytes(LOOP).unwrap()).unwrap();
+    chip8.exec().unwrap();
+    chip8.exec().unwrap();
+
+    chip8.write_ram(0x201, &[0x07]);
+    chip8.exec().unwrap();
+    assert_eq!(chip8.v[0], 7);
+}
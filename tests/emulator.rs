@@ -0,0 +1,85 @@
+use chip8_rs::chip8::Chip8;
+use chip8_rs::emulator::{
+    Emulator, InputEvent, MemoryAudio, MemoryDisplay, NullAudio, NullDisplay, NullInput,
+    ScriptedInput, FRAME,
+};
+use chip8_rs::rom::Rom;
+
+fn machine(rom: &[u8], tick_rate: usize) -> Chip8 {
+    let mut chip8 = Chip8::new();
+    chip8.load(&Rom::from_bytes(rom.to_vec()).unwrap()).unwrap();
+    chip8.tick_rate = tick_rate;
+    chip8
+}
+
+#[test]
+fn scripted_keys_reach_the_machine() {
+    // LD V0, K; LD ST, V0; JP 0x204
+    let chip8 = machine(&[0xF0, 0x0A, 0xF0, 0x18, 0x12, 0x04], 10);
+    let input = ScriptedInput::new([
+        vec![],
+        vec![InputEvent::KeyDown(5)],
+        vec![InputEvent::KeyUp(5)],
+        vec![],
+    ]);
+    let mut emulator = Emulator::new(
+        chip8,
+        MemoryDisplay::default(),
+        MemoryAudio::default(),
+        input,
+    );
+    emulator.run();
+    assert_eq!(emulator.machine.v[0], 5);
+    assert_eq!(emulator.display.frames, 4);
+    assert_eq!(emulator.audio.buzzer, vec![false, false, true, true]);
+}
+
+#[test]
+fn paused_frames_are_presented_without_running() {
+    // ADD V0, 1; JP 0x200
+    let chip8 = machine(&[0x70, 0x01, 0x12, 0x00], 2);
+    let input = ScriptedInput::new([
+        vec![InputEvent::Pause],
+        vec![],
+        vec![InputEvent::Pause],
+        vec![],
+    ]);
+    let mut emulator = Emulator::new(chip8, MemoryDisplay::default(), NullAudio, input);
+    emulator.frame().unwrap();
+    assert!(emulator.display.paused);
+    emulator.run();
+    assert!(!emulator.display.paused);
+    assert_eq!(emulator.display.frames, 4);
+    assert_eq!(emulator.machine.v[0], 2);
+}
+
+#[test]
+fn advance_runs_the_frames_that_fit() {
+    // ADD V0, 1; JP 0x200
+    let chip8 = machine(&[0x70, 0x01, 0x12, 0x00], 2);
+    let mut emulator = Emulator::new(chip8, NullDisplay, NullAudio, NullInput);
+    assert_eq!(emulator.advance(FRAME * 2 + FRAME / 2).unwrap(), 2);
+    assert_eq!(emulator.advance(FRAME / 2).unwrap(), 1);
+    assert_eq!(emulator.machine.v[0], 3);
+}
+
+#[test]
+fn reset_starts_over_after_an_error() {
+    // LD I, 0xFFF; LD V1, [I]
+    let rom = Rom::from_bytes([0xAF, 0xFF, 0xF1, 0x65]).unwrap();
+    let mut chip8 = Chip8::new();
+    chip8.load(&rom).unwrap();
+    chip8.tick_rate = 10;
+    // One frame, which fails, then quit
+    let input = ScriptedInput::new([vec![]]);
+    let mut emulator = Emulator::new(chip8, NullDisplay, NullAudio, input);
+    emulator.run();
+    assert!(emulator.paused);
+    assert!(emulator.error.is_some());
+    assert_eq!(emulator.machine.pc, 0x202);
+
+    emulator.handle(InputEvent::Reset);
+    assert!(!emulator.paused);
+    assert!(emulator.error.is_none());
+    assert_eq!(emulator.machine.pc, 0x200);
+}
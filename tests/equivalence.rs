@@ -0,0 +1,175 @@
+use chip8_rs::chip8::{Chip8, Chip8Error};
+#[cfg(feature = "jit")]
+use chip8_rs::jit::Jit;
+use chip8_rs::quirks::Quirks;
+use chip8_rs::rom::Rom;
+
+// Every way of running a rom has to end in exactly the state the plain interpreter does.
+// See examples/decode_cache.rs and examples/jit.rs for timings.
+
+const ARITHMETIC: [u8; 58] = [
+    0x6E, 0x00, // 200: LD VE, 0
+    0x7E, 0x01, // 202: ADD VE, 1
+    0x80, 0xE0, // 204: LD V0, VE
+    0x81, 0xE4, // 206: ADD V1, VE
+    0x8F, 0x14, // 208: ADD VF, V1
+    0x82, 0xF5, // 20A: SUB V2, VF
+    0x8F, 0x27, // 20C: SUBN VF, V2
+    0x83, 0x16, // 20E: SHR V3, V1
+    0x8F, 0x3E, // 210: SHL VF, V3
+    0x84, 0x31, // 212: OR V4, V3
+    0x85, 0x42, // 214: AND V5, V4
+    0x86, 0x53, // 216: XOR V6, V5
+    0xC7, 0xFF, // 218: RND V7, 0xFF
+    0x87, 0x74, // 21A: ADD V7, V7
+    0xF7, 0x1E, // 21C: ADD I, V7
+    0xF0, 0x29, // 21E: LD F, V0
+    0xF1, 0x15, // 220: LD DT, V1
+    0xF8, 0x07, // 222: LD V8, DT
+    0xF2, 0x18, // 224: LD ST, V2
+    0x50, 0x10, // 226: SE V0, V1
+    0x8F, 0x06, // 228: SHR VF, V0
+    0x91, 0x20, // 22A: SNE V1, V2
+    0x8F, 0xF4, // 22C: ADD VF, VF
+    0x3E, 0x00, // 22E: SE VE, 0
+    0x12, 0x02, // 230: JP 0x202
+    0xA3, 0x00, // 232: LD I, 0x300      every 256 passes
+    0xF7, 0x33, // 234: LD B, V7
+    0xD0, 0x15, // 236: DRW V0, V1, 5
+    0x12, 0x02, // 238: JP 0x202
+];
+
+const SELF_MODIFYING: [u8; 24] = [
+    0x6E, 0x00, // 200: LD VE, 0
+    0x60, 0x6A, // 202: LD V0, 0x6A
+    0x7E, 0x01, // 204: ADD VE, 1
+    0x81, 0xE0, // 206: LD V1, VE
+    0xA2, 0x12, // 208: LD I, 0x212
+    0xF1, 0x55, // 20A: LD [I], V1      rewrites 212 as LD VA, VE
+    0x8B, 0xA4, // 20C: ADD VB, VA
+    0xA3, 0x00, // 20E: LD I, 0x300
+    0xFE, 0x33, // 210: LD B, VE
+    0x00, 0x00, // 212: rewritten above
+    0xD0, 0x13, // 214: DRW V0, V1, 3
+    0x12, 0x04, // 216: JP 0x204
+];
+
+const PRESETS: [fn() -> Quirks; 4] = [
+    Quirks::default,
+    Quirks::chip8,
+    Quirks::superchip,
+    Quirks::xochip,
+];
+
+// A high tick rate, and ones that end frames in the middle of the loops
+const TICK_RATES: [usize; 3] = [1000, 7, 1];
+
+const FRAMES: usize = 300;
+
+fn machine(rom: &[u8], quirks: Quirks, tick_rate: usize) -> Chip8 {
+    let mut chip8 = Chip8::new();
+    chip8.load(&Rom::from_bytes(rom.to_vec()).unwrap()).unwrap();
+    chip8.seed(1);
+    chip8.quirks = quirks;
+    chip8.tick_rate = tick_rate;
+    chip8
+}
+
+// The saved state after `FRAMES` frames, or where it stopped
+fn run(
+    mut chip8: Chip8,
+    mut frame: impl FnMut(&mut Chip8) -> Result<(), Chip8Error>,
+) -> (Vec<u8>, Option<Chip8Error>) {
+    for _ in 0..FRAMES {
+        if let Err(e) = frame(&mut chip8) {
+            return (chip8.save_state(), Some(e));
+        }
+    }
+    (chip8.save_state(), None)
+}
+
+fn plain(rom: &[u8], quirks: Quirks, tick_rate: usize) -> (Vec<u8>, Option<Chip8Error>) {
+    let mut chip8 = machine(rom, quirks, tick_rate);
+    chip8.decode_cache = false;
+    run(chip8, Chip8::frame)
+}
+
+fn each_run(mut check: impl FnMut(&[u8], Quirks, usize)) {
+    for rom in [&ARITHMETIC[..], &SELF_MODIFYING[..]] {
+        for quirks in PRESETS {
+            for tick_rate in TICK_RATES {
+                check(rom, quirks(), tick_rate);
+            }
+        }
+    }
+}
+
+#[test]
+fn decode_cache_matches_the_interpreter() {
+    each_run(|rom, quirks, tick_rate| {
+        let cached = run(machine(rom, quirks, tick_rate), Chip8::frame);
+        assert!(
+            cached == plain(rom, quirks, tick_rate),
+            "{:?} at {} per frame",
+            quirks,
+            tick_rate
+        );
+    });
+}
+
+// ADD VB, <frame number> over the ADD VB, VA at 20C before every frame, and a reset now
+// and then, which puts the rom back
+fn patched(
+    frames: &mut usize,
+    chip8: &mut Chip8,
+    frame: impl FnOnce(&mut Chip8) -> Result<(), Chip8Error>,
+) -> Result<(), Chip8Error> {
+    *frames += 1;
+    if frames.is_multiple_of(64) {
+        chip8.reset();
+    }
+    chip8.write_ram(0x20C, &[0x7B, *frames as u8]);
+    frame(chip8)
+}
+
+fn plain_patched() -> (Vec<u8>, Option<Chip8Error>) {
+    let mut chip8 = machine(&SELF_MODIFYING, Quirks::default(), 7);
+    chip8.decode_cache = false;
+    let mut frames = 0;
+    run(chip8, |chip8| patched(&mut frames, chip8, Chip8::frame))
+}
+
+#[test]
+fn decode_cache_follows_patched_ram() {
+    let chip8 = machine(&SELF_MODIFYING, Quirks::default(), 7);
+    let mut frames = 0;
+    let cached = run(chip8, |chip8| patched(&mut frames, chip8, Chip8::frame));
+    assert!(cached == plain_patched());
+}
+
+#[cfg(feature = "jit")]
+#[test]
+fn jit_matches_the_interpreter() {
+    let mut jit = Jit::new().unwrap();
+    each_run(|rom, quirks, tick_rate| {
+        let compiled = run(machine(rom, quirks, tick_rate), |chip8| jit.frame(chip8));
+        assert!(
+            compiled == plain(rom, quirks, tick_rate),
+            "{:?} at {} per frame",
+            quirks,
+            tick_rate
+        );
+    });
+}
+
+#[cfg(feature = "jit")]
+#[test]
+fn jit_follows_patched_ram() {
+    let mut jit = Jit::new().unwrap();
+    let chip8 = machine(&SELF_MODIFYING, Quirks::default(), 7);
+    let mut frames = 0;
+    let compiled = run(chip8, |chip8| {
+        patched(&mut frames, chip8, |chip8| jit.frame(chip8))
+    });
+    assert!(compiled == plain_patched());
+}
@@ -0,0 +1,94 @@
+use chip8_rs::chip8::Chip8;
+use chip8_rs::heatmap::{Heatmap, HEATMAP_SIZE};
+use chip8_rs::profile::{Profile, StackLayout};
+use chip8_rs::rom::Rom;
+
+fn load(chip8: &mut Chip8, rom: &[u8], tick_rate: usize) {
+    chip8.load(&Rom::from_bytes(rom.to_vec()).unwrap()).unwrap();
+    chip8.tick_rate = tick_rate;
+}
+
+fn pixel(heatmap: &Heatmap, addr: usize) -> [u8; 4] {
+    heatmap.rgba()[addr * 4..addr * 4 + 4].try_into().unwrap()
+}
+
+#[test]
+fn counts_fade_every_frame() {
+    // JP 0x200
+    let mut chip8 = Chip8::new();
+    load(&mut chip8, &[0x12, 0x00], 1);
+    let mut heatmap = Heatmap::with_decay(0.5);
+    heatmap.frame(&mut chip8).unwrap();
+    assert_eq!(heatmap.executes[0x200], 0.5);
+    assert_eq!(heatmap.executes[0x201], 0.5);
+    heatmap.frame(&mut chip8).unwrap();
+    assert_eq!(heatmap.executes[0x200], 0.75);
+    // Without running anything, counts only fade
+    heatmap.end_frame();
+    heatmap.end_frame();
+    assert_eq!(heatmap.executes[0x200], 0.1875);
+
+    heatmap.clear();
+    assert!(heatmap.executes.iter().all(|count| *count == 0.0));
+}
+
+#[test]
+fn writes_are_red_reads_green_and_executes_blue() {
+    let rom = [
+        0xA3, 0x00, // 200: LD I, 0x300
+        0xF0, 0x55, // 202: LD [I], V0
+        0xF1, 0x65, // 204: LD V1, [I]
+        0x12, 0x06, // 206: JP 0x206
+    ];
+    let mut chip8 = Chip8::new();
+    load(&mut chip8, &rom, 4);
+    let mut heatmap = Heatmap::with_decay(1.0);
+    heatmap.frame(&mut chip8).unwrap();
+
+    // A count of 1 is a fifth of the way to full brightness
+    assert_eq!(heatmap.rgba().len(), HEATMAP_SIZE * HEATMAP_SIZE * 4);
+    assert_eq!(pixel(&heatmap, 0x300), [51, 51, 0, 0xFF]);
+    assert_eq!(pixel(&heatmap, 0x301), [0, 51, 0, 0xFF]);
+    assert_eq!(pixel(&heatmap, 0x302), [0, 0, 0, 0xFF]);
+    assert_eq!(pixel(&heatmap, 0x200), [0, 0, 51, 0xFF]);
+
+    // Brighter the more often, but never saturating
+    heatmap.executes[0x200] = 4.0;
+    assert_eq!(pixel(&heatmap, 0x200), [0, 0, 127, 0xFF]);
+    heatmap.executes[0x200] = 1e6;
+    assert_eq!(pixel(&heatmap, 0x200), [0, 0, 254, 0xFF]);
+}
+
+#[test]
+fn in_ram_stack_shows_as_written_and_read() {
+    // CALL 0x204; JP 0x202; RET
+    let rom = [0x22, 0x04, 0x12, 0x02, 0x00, 0xEE];
+    let mut chip8 =
+        Chip8::with_profile(Profile::default().with_stack(StackLayout::in_ram(12, 0xEA0)));
+    load(&mut chip8, &rom, 2);
+    let mut heatmap = Heatmap::with_decay(1.0);
+    heatmap.frame(&mut chip8).unwrap();
+    assert_eq!(pixel(&heatmap, 0xEA0), [51, 51, 0, 0xFF]);
+    assert_eq!(pixel(&heatmap, 0xEA1), [51, 51, 0, 0xFF]);
+}
+
+#[test]
+fn png_scales_every_address_to_a_block() {
+    let mut heatmap = Heatmap::new();
+    heatmap.writes[1] = 4.0;
+    let mut png = Vec::new();
+    heatmap.write_png(&mut png, 3).unwrap();
+
+    let mut reader = png::Decoder::new(std::io::Cursor::new(png))
+        .read_info()
+        .unwrap();
+    let mut data = vec![0; reader.output_buffer_size().unwrap()];
+    let info = reader.next_frame(&mut data).unwrap();
+    assert_eq!((info.width, info.height), (192, 192));
+    assert_eq!(info.color_type, png::ColorType::Rgba);
+    let at = |x: usize, y: usize| &data[(y * 192 + x) * 4..(y * 192 + x) * 4 + 4];
+    assert_eq!(at(2, 2), [0, 0, 0, 0xFF]);
+    assert_eq!(at(3, 0), [127, 0, 0, 0xFF]);
+    assert_eq!(at(5, 2), [127, 0, 0, 0xFF]);
+    assert_eq!(at(6, 0), [0, 0, 0, 0xFF]);
+}
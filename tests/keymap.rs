@@ -0,0 +1,104 @@
+use chip8_rs::keymap::{KeyMap, KEYMAP_AXIS_THRESHOLD};
+use chip8_rs::rom::Rom;
+use std::collections::BTreeMap;
+
+#[test]
+fn default_map_round_trips_through_toml_and_json() {
+    let keymap = KeyMap::default();
+    assert_eq!(
+        KeyMap::from_toml(&keymap.to_toml().unwrap()).unwrap(),
+        keymap
+    );
+    assert_eq!(
+        KeyMap::from_json(&keymap.to_json().unwrap()).unwrap(),
+        keymap
+    );
+}
+
+#[test]
+fn rom_overrides_round_trip() {
+    let mut keymap = KeyMap::empty();
+    keymap.keys.insert("space".into(), 5);
+    let mut overrides = KeyMap::empty();
+    overrides.keys.insert("up".into(), 2);
+    keymap.roms.insert("00".repeat(20), overrides);
+    assert_eq!(
+        KeyMap::from_toml(&keymap.to_toml().unwrap()).unwrap(),
+        keymap
+    );
+    assert_eq!(
+        KeyMap::from_json(&keymap.to_json().unwrap()).unwrap(),
+        keymap
+    );
+}
+
+#[test]
+fn bindings_past_key_f_are_rejected() {
+    assert!(KeyMap::from_toml("[keys]\nq = 15").is_ok());
+    let e = KeyMap::from_toml("[keys]\nq = 20").unwrap_err();
+    assert!(e.to_string().contains("'q' is bound to 20"), "{}", e);
+    assert!(KeyMap::from_json(r#"{"buttons": {"a": 16}}"#).is_err());
+    assert!(KeyMap::from_json(r#"{"axes": {"leftx-": 255}}"#).is_err());
+    assert!(KeyMap::from_toml("[roms.abc.keys]\nspace = 17").is_err());
+}
+
+#[test]
+fn names_match_case_insensitively() {
+    let keymap = KeyMap::from_toml("[keys]\nQ = 4\nup = 2\n\n[buttons]\nDPUP = 2").unwrap();
+    assert_eq!(keymap.key("q"), Some(4));
+    assert_eq!(keymap.key("Q"), Some(4));
+    assert_eq!(keymap.key("Up"), Some(2));
+    assert_eq!(keymap.button("dpup"), Some(2));
+    assert_eq!(keymap.key("w"), None);
+}
+
+#[test]
+fn axes_only_count_past_the_threshold() {
+    let keymap = KeyMap::default();
+    assert_eq!(keymap.axis("leftx", -KEYMAP_AXIS_THRESHOLD), Some(4));
+    assert_eq!(keymap.axis("leftx", KEYMAP_AXIS_THRESHOLD), Some(6));
+    assert_eq!(keymap.axis("leftx", KEYMAP_AXIS_THRESHOLD - 1), None);
+    assert_eq!(keymap.axis("lefty", i16::MIN), Some(2));
+}
+
+#[test]
+fn overrides_apply_only_to_their_rom() {
+    let rom = Rom::from_bytes([0x12, 0x00]).unwrap();
+    let other = Rom::from_bytes([0x12, 0x02]).unwrap();
+    let toml = format!(
+        "[keys]\nq = 4\nw = 5\n\n[roms.{}.keys]\nQ = 6\nspace = 5",
+        rom.sha1_hex().to_ascii_uppercase()
+    );
+    let keymap = KeyMap::from_toml(&toml).unwrap();
+
+    let for_rom = keymap.for_rom(&rom);
+    assert_eq!(for_rom.key("q"), Some(6));
+    assert_eq!(for_rom.key("w"), Some(5));
+    assert_eq!(for_rom.key("space"), Some(5));
+    // Replaced, not bound twice
+    assert_eq!(for_rom.keys.len(), 3);
+    assert!(for_rom.roms.is_empty());
+
+    let for_other = keymap.for_rom(&other);
+    assert_eq!(for_other.key("q"), Some(4));
+    assert_eq!(for_other.key("space"), None);
+}
+
+#[test]
+fn rom_controls_bind_keys_buttons_and_sticks() {
+    let controls: BTreeMap<String, u8> = [("up", 5), ("a", 6), ("start", 1), ("b", 0x10)]
+        .into_iter()
+        .map(|(control, key)| (control.to_string(), key))
+        .collect();
+    let keymap = KeyMap::default().with_rom_controls(&controls);
+    assert_eq!(keymap.key("up"), Some(5));
+    assert_eq!(keymap.button("dpup"), Some(5));
+    assert_eq!(keymap.axis("lefty", -KEYMAP_AXIS_THRESHOLD), Some(5));
+    assert_eq!(keymap.key("space"), Some(6));
+    assert_eq!(keymap.button("a"), Some(6));
+    // Unknown controls and keys past F are left out, the rest is untouched
+    assert_eq!(keymap.key("left shift"), None);
+    assert_eq!(keymap.button("b"), Some(0));
+    assert_eq!(keymap.button("start"), Some(0xF));
+    assert_eq!(keymap.key("q"), Some(4));
+}
@@ -0,0 +1,43 @@
+use chip8_rs::chip8::Chip8;
+use chip8_rs::rom::Rom;
+
+// LD V0, K; JP 0x202
+const WAIT_FOR_KEY: [u8; 4] = [0xF0, 0x0A, 0x12, 0x02];
+
+fn waiting() -> Chip8 {
+    let mut chip8 = Chip8::new();
+    chip8.load(&Rom::from_bytes(WAIT_FOR_KEY).unwrap()).unwrap();
+    chip8
+}
+
+#[test]
+fn fx0a_finishes_when_a_key_is_pressed_and_released() {
+    let mut chip8 = waiting();
+    chip8.frame().unwrap();
+    assert_eq!(chip8.pc, 0x200);
+
+    chip8.key_down(7);
+    chip8.frame().unwrap();
+    assert_eq!(chip8.pc, 0x200);
+    chip8.key_up(7);
+    chip8.frame().unwrap();
+    assert_eq!(chip8.pc, 0x202);
+    assert_eq!(chip8.v[0], 7);
+}
+
+#[test]
+fn fx0a_ignores_a_key_held_when_it_started() {
+    let mut chip8 = waiting();
+    chip8.key_down(3);
+    chip8.frame().unwrap();
+    chip8.key_up(3);
+    chip8.frame().unwrap();
+    assert_eq!(chip8.pc, 0x200);
+
+    // Pressed and released between two frames still counts
+    chip8.key_down(5);
+    chip8.key_up(5);
+    chip8.frame().unwrap();
+    assert_eq!(chip8.pc, 0x202);
+    assert_eq!(chip8.v[0], 5);
+}
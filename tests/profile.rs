@@ -0,0 +1,130 @@
+use chip8_rs::chip8::{Chip8, Chip8Error, CHIP8_FONTSET, CHIP8_RAM};
+use chip8_rs::font::FontSet;
+use chip8_rs::profile::{Profile, StackLayout};
+use chip8_rs::rom::Rom;
+
+#[test]
+fn font_past_the_end_of_ram_is_moved_back() {
+    let chip8 = Chip8::with_profile(Profile::default().with_font(FontSet::Octo, 0xFFF));
+    let font_addr = CHIP8_RAM - CHIP8_FONTSET.len();
+    assert_eq!(chip8.profile.font_addr, font_addr);
+    assert_eq!(chip8.ram()[font_addr..], FontSet::Octo.glyphs());
+}
+
+#[test]
+fn eti660_roms_load_and_start_at_0x600() {
+    // LD V0, F; LD F, V0
+    let rom = Rom::from_bytes([0x60, 0x0F, 0xF0, 0x29]).unwrap();
+    let mut chip8 = Chip8::with_profile("eti660".parse().unwrap());
+    chip8.load(&rom).unwrap();
+    assert_eq!(chip8.pc, 0x600);
+    assert_eq!(chip8.ram()[0x600..0x604], *rom.bytes());
+    chip8.exec().unwrap();
+    chip8.exec().unwrap();
+    assert_eq!(chip8.i, chip8.profile.font_addr + 0xF * 5);
+}
+
+#[test]
+fn font_past_the_load_address_survives_loading() {
+    let mut chip8 = Chip8::with_profile(Profile::default().with_font(FontSet::Octo, 0x800));
+    // LD V0, 1
+    chip8.load(&Rom::from_bytes([0x60, 0x01]).unwrap()).unwrap();
+    assert_eq!(
+        chip8.ram()[0x800..0x800 + CHIP8_FONTSET.len()],
+        FontSet::Octo.glyphs()
+    );
+    assert_eq!(chip8.ram()[0x200..0x202], [0x60, 0x01]);
+    chip8.reset();
+    assert_eq!(
+        chip8.ram()[0x800..0x800 + CHIP8_FONTSET.len()],
+        FontSet::Octo.glyphs()
+    );
+}
+
+#[test]
+fn loading_another_rom_clears_the_last_one_but_not_the_font() {
+    let mut chip8 = Chip8::with_profile(Profile::default().with_font(FontSet::Vip, 0x300));
+    chip8
+        .load(&Rom::from_bytes(vec![0xAA; 0x200]).unwrap())
+        .unwrap();
+    chip8.load(&Rom::from_bytes([0x60, 0x01]).unwrap()).unwrap();
+    assert!(chip8.ram()[0x202..0x300].iter().all(|b| *b == 0));
+    assert_eq!(
+        chip8.ram()[0x300..0x300 + CHIP8_FONTSET.len()],
+        FontSet::Vip.glyphs()
+    );
+}
+
+fn with_stack(stack: StackLayout, rom: &[u8]) -> Chip8 {
+    let mut chip8 = Chip8::with_profile(Profile::default().with_stack(stack));
+    chip8.load(&Rom::from_bytes(rom.to_vec()).unwrap()).unwrap();
+    chip8
+}
+
+#[test]
+fn fx55_over_an_in_ram_stack_changes_where_00ee_returns() {
+    let rom = [
+        0x22, 0x06, // 200: CALL 0x206
+        0x12, 0x02, // 202: JP 0x202
+        0x12, 0x04, // 204: JP 0x204
+        0x60, 0x02, // 206: LD V0, 0x02
+        0x61, 0x04, // 208: LD V1, 0x04
+        0xAE, 0xA0, // 20A: LD I, 0xEA0
+        0xF1, 0x55, // 20C: LD [I], V1      0x204 over the return address
+        0x00, 0xEE, // 20E: RET
+    ];
+    let mut chip8 = with_stack(StackLayout::in_ram(12, 0xEA0), &rom);
+    chip8.exec().unwrap();
+    assert_eq!(chip8.ram()[0xEA0..0xEA2], [0x02, 0x02]);
+    assert_eq!(chip8.call_stack(), vec![0x202]);
+
+    for _ in 0..4 {
+        chip8.exec().unwrap();
+    }
+    assert_eq!(chip8.call_stack(), vec![0x204]);
+    chip8.exec().unwrap();
+    assert_eq!(chip8.pc, 0x204);
+    assert_eq!(chip8.sp, 0);
+}
+
+#[test]
+fn stack_outside_ram_is_out_of_reach_of_fx55() {
+    // LD I, 0xEA0; CALL 0x208; JP 0x206; JP 0x206; LD [I], VF; RET
+    let rom = [
+        0xAE, 0xA0, 0x22, 0x08, 0x12, 0x06, 0x12, 0x06, 0xFF, 0x55, 0x00, 0xEE,
+    ];
+    let mut chip8 = with_stack(StackLayout::new(12), &rom);
+    chip8.v = [0xAB; 16];
+    for _ in 0..4 {
+        chip8.exec().unwrap();
+    }
+    assert_eq!(chip8.pc, 0x204);
+}
+
+#[test]
+fn calls_past_the_stack_depth_overflow() {
+    // CALL 0x200
+    let mut chip8 = with_stack(StackLayout::new(2), &[0x22, 0x00]);
+    chip8.exec().unwrap();
+    chip8.exec().unwrap();
+    assert_eq!(chip8.exec(), Err(Chip8Error::StackOverflow { pc: 0x200 }));
+    assert_eq!(chip8.sp, 2);
+    assert_eq!(chip8.stack_depth(), 2);
+}
+
+#[test]
+fn return_without_a_call_underflows() {
+    let mut chip8 = with_stack(StackLayout::in_ram(12, 0xEA0), &[0x00, 0xEE]);
+    assert_eq!(chip8.exec(), Err(Chip8Error::StackUnderflow { pc: 0x200 }));
+}
+
+#[test]
+fn stack_layouts_parse() {
+    assert_eq!("16".parse(), Ok(StackLayout::new(16)));
+    assert_eq!("12@ea0".parse(), Ok(StackLayout::in_ram(12, 0xEA0)));
+    assert_eq!("12@0xEA0".parse(), Ok(StackLayout::in_ram(12, 0xEA0)));
+    assert_eq!(StackLayout::in_ram(12, 0xEA0).to_string(), "12@0xea0");
+    for bad in ["0", "65", "deep", "12@", "12@zz", "12@ff0"] {
+        assert!(bad.parse::<StackLayout>().is_err(), "{}", bad);
+    }
+}
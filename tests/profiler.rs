@@ -0,0 +1,100 @@
+use chip8_rs::chip8::Chip8;
+use chip8_rs::profiler::{Profiler, SubroutineStats};
+use chip8_rs::rom::Rom;
+
+// main calls 0x206 twice, which calls 0x20c each time, then loops at 0x204
+const NESTED: [u8; 16] = [
+    0x22, 0x06, // 200: CALL 0x206
+    0x22, 0x06, // 202: CALL 0x206
+    0x12, 0x04, // 204: JP 0x204
+    0x22, 0x0C, // 206: CALL 0x20c
+    0x60, 0x01, // 208: LD V0, 0x01
+    0x00, 0xEE, // 20A: RET
+    0x61, 0x02, // 20C: LD V1, 0x02
+    0x00, 0xEE, // 20E: RET
+];
+
+// Runs one frame of `tick_rate` instructions
+fn profile(rom: &[u8], tick_rate: usize) -> Profiler {
+    let mut chip8 = Chip8::new();
+    chip8.load(&Rom::from_bytes(rom.to_vec()).unwrap()).unwrap();
+    chip8.tick_rate = tick_rate;
+    let mut profiler = Profiler::new();
+    profiler.frame(&mut chip8).unwrap();
+    profiler
+}
+
+fn output(write: impl FnOnce(&mut Vec<u8>) -> std::io::Result<()>) -> String {
+    let mut out = Vec::new();
+    write(&mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn folded_stacks_follow_nested_calls() {
+    // Up to the first time round the loop at 0x204
+    let profiler = profile(&NESTED, 13);
+    assert_eq!(profiler.instructions, 13);
+    assert_eq!(
+        output(|w| profiler.write_folded(w)),
+        "main 3\nmain;sub_0x206 6\nmain;sub_0x206;sub_0x20c 4\n"
+    );
+}
+
+#[test]
+fn subroutines_count_their_own_and_their_callees_cycles() {
+    let profiler = profile(&NESTED, 13);
+    assert_eq!(
+        profiler.subroutines[&Some(0x206)],
+        SubroutineStats {
+            calls: 2,
+            self_cycles: 6,
+            total_cycles: 10
+        }
+    );
+    assert_eq!(
+        profiler.subroutines[&Some(0x20C)],
+        SubroutineStats {
+            calls: 2,
+            self_cycles: 4,
+            total_cycles: 4
+        }
+    );
+    assert_eq!(profiler.subroutines[&None].self_cycles, 3);
+    assert_eq!(profiler.subroutines[&None].total_cycles, 13);
+    assert_eq!(profiler.calls[&(None, 0x206)], 2);
+    assert_eq!(profiler.calls[&(Some(0x206), 0x20C)], 2);
+
+    let graph = output(|w| profiler.write_call_graph(w));
+    assert!(graph.contains("    called from sub_0x206 (2)"));
+    assert!(graph.contains("    calls sub_0x20c (2)"));
+}
+
+#[test]
+fn flat_report_counts_opcode_patterns() {
+    let profiler = profile(&NESTED, 13);
+    let counts: Vec<(&str, u64)> = profiler.by_family.iter().map(|(f, n)| (*f, *n)).collect();
+    assert_eq!(counts, [("00EE", 4), ("1nnn", 1), ("2nnn", 4), ("6xkk", 4)]);
+
+    let flat = output(|w| profiler.write_flat(w));
+    assert!(flat.starts_with("13 instructions in 1 frames, 0 frames waiting for a key (Fx0A)\n"));
+    // The addresses run twice come first, lowest first
+    assert!(flat.contains(
+        "hot spots\n     count       %  addr   opcode\n         2  15.38%  0x206  220c\n"
+    ));
+    assert!(flat.contains(&format!(
+        "{:>10} {:>6.2}%  {}\n",
+        4,
+        4.0 * 100.0 / 13.0,
+        "2nnn"
+    )));
+    assert!(flat.ends_with(&format!("{:>10} {:>6.2}%  {}\n", 1, 100.0 / 13.0, "1nnn")));
+}
+
+#[test]
+fn frames_spent_in_fx0a_are_counted() {
+    // LD V0, K
+    let profiler = profile(&[0xF0, 0x0A], 10);
+    assert_eq!(profiler.key_wait_frames, 1);
+    assert_eq!(profiler.by_family["Fx0A"], 10);
+}
@@ -0,0 +1,172 @@
+use chip8_rs::chip8::{Chip8, Chip8Error};
+use chip8_rs::machine::{Framebuffer, Machine};
+use chip8_rs::recorder::{Recorder, RECORDER_FPS, RECORDER_SAMPLE_RATE};
+use chip8_rs::state::StateError;
+
+// A screen of any size, for recording without running anything
+struct Screen {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+    sound: bool,
+}
+
+impl Screen {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width * height],
+            sound: false,
+        }
+    }
+}
+
+impl Machine for Screen {
+    type Error = Chip8Error;
+
+    fn step(&mut self) -> Result<(), Chip8Error> {
+        Ok(())
+    }
+
+    fn frame(&mut self) -> Result<(), Chip8Error> {
+        Ok(())
+    }
+
+    fn tick_timers(&mut self) {}
+
+    fn key_down(&mut self, _key: usize) {}
+
+    fn key_up(&mut self, _key: usize) {}
+
+    fn framebuffer(&self) -> Framebuffer<'_> {
+        Framebuffer {
+            width: self.width,
+            height: self.height,
+            planes: 1,
+            pixels: &self.pixels,
+        }
+    }
+
+    fn sound(&self) -> bool {
+        self.sound
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        Vec::new()
+    }
+
+    fn load_state(&mut self, _state: &[u8]) -> Result<(), StateError> {
+        Ok(())
+    }
+
+    fn reset(&mut self) {}
+
+    fn speed(&self) -> usize {
+        1
+    }
+
+    fn set_speed(&mut self, _speed: usize) {}
+}
+
+// (width, height, delay) of every frame of the GIF
+fn decode_gif(gif: &[u8]) -> Vec<(u16, u16, u16)> {
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::Indexed);
+    let mut decoder = options.read_info(gif).unwrap();
+    let mut frames = Vec::new();
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        frames.push((frame.width, frame.height, frame.delay));
+    }
+    frames
+}
+
+#[test]
+fn gif_frames_are_never_shorter_than_two_centiseconds() {
+    let mut screen = Screen::new(64, 32);
+    let mut recorder = Recorder::new();
+    // A different picture every frame, for a second
+    for n in 0..RECORDER_FPS as usize {
+        screen.pixels[n] = 1;
+        recorder.capture_frame(&screen);
+    }
+    let mut gif = Vec::new();
+    recorder.write_gif(&mut gif, 1).unwrap();
+
+    let frames = decode_gif(&gif);
+    assert!(frames.iter().all(|(_, _, delay)| *delay >= 2));
+    // At most 50 frames a second, and still a second long
+    assert!(frames.len() <= 50);
+    let total: u16 = frames.iter().map(|(_, _, delay)| delay).sum();
+    assert_eq!(total, 100);
+}
+
+#[test]
+fn identical_gif_frames_are_merged() {
+    let mut screen = Screen::new(64, 32);
+    let mut recorder = Recorder::new();
+    for _ in 0..30 {
+        recorder.capture_frame(&screen);
+    }
+    screen.pixels[0] = 1;
+    for _ in 0..30 {
+        recorder.capture_frame(&screen);
+    }
+    let mut gif = Vec::new();
+    recorder.write_gif(&mut gif, 2).unwrap();
+    assert_eq!(decode_gif(&gif), vec![(128, 64, 50), (128, 64, 50)]);
+}
+
+#[test]
+fn raw_frames_are_scaled_to_the_largest_resolution() {
+    let mut lores = Screen::new(2, 1);
+    lores.pixels = vec![1, 0];
+    let mut hires = Screen::new(4, 2);
+    hires.pixels = vec![0, 1, 0, 1, 1, 0, 1, 0];
+    let mut recorder = Recorder::new();
+    recorder.capture_frame(&lores);
+    recorder.capture_frame(&hires);
+    assert_eq!(recorder.raw_size(), (4, 2));
+
+    let mut raw = Vec::new();
+    recorder.write_raw_frames(&mut raw).unwrap();
+    assert_eq!(
+        raw,
+        [
+            [0xFF, 0xFF, 0, 0, 0xFF, 0xFF, 0, 0],
+            [0, 0xFF, 0, 0xFF, 0xFF, 0, 0xFF, 0]
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn wav_has_a_sample_per_frame_and_a_matching_header() {
+    let mut chip8 = Chip8::new();
+    let mut recorder = Recorder::new();
+    for n in 0..90 {
+        chip8.sound_timer = (n % 2) as u8;
+        recorder.capture_frame(&chip8);
+    }
+    let mut wav = Vec::new();
+    recorder.write_wav(&mut wav).unwrap();
+
+    let u16_at = |at: usize| u16::from_le_bytes(wav[at..at + 2].try_into().unwrap());
+    let u32_at = |at: usize| u32::from_le_bytes(wav[at..at + 4].try_into().unwrap());
+    let samples = 90 * RECORDER_SAMPLE_RATE as usize / RECORDER_FPS as usize;
+    assert_eq!(&wav[0..4], b"RIFF");
+    assert_eq!(u32_at(4) as usize, wav.len() - 8);
+    assert_eq!(&wav[8..16], b"WAVEfmt ");
+    assert_eq!(u16_at(20), 1); // PCM
+    assert_eq!(u16_at(22), 1); // mono
+    assert_eq!(u32_at(24), RECORDER_SAMPLE_RATE);
+    assert_eq!(u16_at(34), 16);
+    assert_eq!(&wav[36..40], b"data");
+    assert_eq!(u32_at(40) as usize, samples * 2);
+    assert_eq!(wav.len(), 44 + samples * 2);
+    // The first frame is silent, the second isn't
+    let sample = |n: usize| i16::from_le_bytes(wav[44 + 2 * n..46 + 2 * n].try_into().unwrap());
+    let per_frame = RECORDER_SAMPLE_RATE as usize / RECORDER_FPS as usize;
+    assert!((0..per_frame).all(|n| sample(n) == 0));
+    assert!((per_frame..2 * per_frame).any(|n| sample(n) != 0));
+}
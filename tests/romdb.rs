@@ -0,0 +1,135 @@
+use chip8_rs::chip8::Chip8;
+use chip8_rs::quirks::Quirks;
+use chip8_rs::rom::{Platform, Rom};
+use chip8_rs::romdb::{select_programs, RomDatabase};
+
+// LD V0, 1; JP 0x202
+const ROM: [u8; 4] = [0x60, 0x01, 0x12, 0x02];
+
+// One program in the format of chip-8-database's programs.json, keyed by the rom's hash
+fn programs_json(sha1: &str) -> String {
+    format!(
+        r##"[
+            {{
+                "title": "Test Program",
+                "description": "Loops forever.",
+                "release": "2024",
+                "authors": ["Someone"],
+                "roms": {{
+                    "{}": {{
+                        "file": "test.ch8",
+                        "platforms": ["hybridVIP", "originalChip8"],
+                        "quirkyPlatforms": {{ "originalChip8": {{ "shift": true }} }},
+                        "tickrate": 30,
+                        "keys": {{ "up": 5, "a": 6 }},
+                        "colors": {{
+                            "pixels": ["#000000", "#ffcc00"],
+                            "buzzer": "#990000"
+                        }}
+                    }}
+                }}
+            }}
+        ]"##,
+        sha1
+    )
+}
+
+// Every hash in the bundled database, as written by examples/romdb_import.rs, finds its
+// program with the speed listed for it
+#[test]
+fn bundled_hashes_resolve_to_their_programs() {
+    let programs: Vec<serde_json::Value> =
+        serde_json::from_str(include_str!("../src/romdb.json")).unwrap();
+    for program in programs.iter() {
+        for (sha1, entry) in program["roms"].as_object().unwrap() {
+            let info = RomDatabase::bundled()
+                .lookup_sha1(sha1)
+                .unwrap_or_else(|| panic!("{} is not found", sha1));
+            assert_eq!(info.title, program["title"].as_str().unwrap());
+            assert_eq!(
+                info.tick_rate,
+                entry["tickrate"].as_u64().map(|rate| rate as usize)
+            );
+        }
+    }
+}
+
+#[test]
+fn selected_programs_resolve_the_given_roms() {
+    let rom = Rom::from_bytes(ROM).unwrap();
+    let other = "0".repeat(40);
+    let json = programs_json(&rom.sha1_hex().to_uppercase()).replace(
+        r#""roms": {"#,
+        &format!(r#""roms": {{ "{}": {{ "platforms": ["xochip"] }},"#, other),
+    );
+    let json = format!(
+        r#"[{}, {{ "title": "Other", "roms": {{ "{}": {{ "platforms": ["xochip"] }} }} }}]"#,
+        json.trim().trim_start_matches('[').trim_end_matches(']'),
+        "1".repeat(40)
+    );
+    assert_eq!(RomDatabase::from_json(&json).unwrap().len(), 3);
+
+    let programs = select_programs(&json, std::slice::from_ref(&rom)).unwrap();
+    assert_eq!(programs.len(), 1);
+    assert_eq!(programs[0]["roms"].as_object().unwrap().len(), 1);
+
+    let db = RomDatabase::from_json(&serde_json::to_string(&programs).unwrap()).unwrap();
+    assert_eq!(db.len(), 1);
+    let info = db.lookup(&rom).unwrap();
+    assert_eq!(info.title, "Test Program");
+    assert_eq!(info.quirks, Quirks::chip8());
+    assert_eq!(info.tick_rate, Some(30));
+    assert!(db.lookup_sha1(&other).is_none());
+}
+
+#[test]
+fn rom_hash_resolves_to_info() {
+    let rom = Rom::from_bytes(ROM).unwrap();
+    // Upstream keys are lowercase, but any case matches
+    let db = RomDatabase::from_json(&programs_json(&rom.sha1_hex().to_uppercase())).unwrap();
+    assert_eq!(db.len(), 1);
+
+    let info = db.lookup(&rom).expect("rom is in the database");
+    assert_eq!(info.title, "Test Program");
+    assert_eq!(info.file.as_deref(), Some("test.ch8"));
+    assert_eq!(info.platform, Platform::Chip8);
+    // hybridVIP comes first, so originalChip8's override doesn't apply
+    assert_eq!(info.quirks, Quirks::chip8());
+    assert_eq!(info.tick_rate, Some(30));
+    assert_eq!(info.keys.get("up"), Some(&5));
+    assert_eq!(info.keys.get("a"), Some(&6));
+    let colors = info.colors.as_ref().unwrap();
+    assert_eq!(colors.pixels, vec![[0x00, 0x00, 0x00], [0xFF, 0xCC, 0x00]]);
+    assert_eq!(colors.buzzer, Some([0x99, 0x00, 0x00]));
+    assert_eq!(colors.silence, None);
+
+    let mut chip8 = Chip8::new();
+    chip8.load(&rom).unwrap();
+    chip8.apply_rom_info(info);
+    assert_eq!(chip8.quirks, Quirks::chip8());
+    assert_eq!(chip8.tick_rate, 30);
+}
+
+#[test]
+fn quirky_platform_overrides_apply() {
+    let rom = Rom::from_bytes(ROM).unwrap();
+    let json = programs_json(&rom.sha1_hex()).replace(r#""hybridVIP", "#, "");
+    let db = RomDatabase::from_json(&json).unwrap();
+    let info = db.lookup(&rom).unwrap();
+    assert!(info.quirks.shifting);
+    assert_eq!(
+        Quirks {
+            shifting: false,
+            ..info.quirks
+        },
+        Quirks::chip8()
+    );
+}
+
+#[test]
+fn unknown_rom_has_no_info() {
+    let rom = Rom::from_bytes(ROM).unwrap();
+    let other = Rom::from_bytes([0x12, 0x00]).unwrap();
+    let db = RomDatabase::from_json(&programs_json(&rom.sha1_hex())).unwrap();
+    assert!(db.lookup(&other).is_none());
+}
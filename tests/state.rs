@@ -0,0 +1,86 @@
+use chip8_rs::chip8::{Chip8, CHIP8_RAM};
+use chip8_rs::rom::Rom;
+use chip8_rs::state::StateError;
+
+// JP 0x200
+const LOOP: [u8; 2] = [0x12, 0x00];
+
+// LD V0, 0xFF; ADD I, V0; JP 0x202
+const RAISE_I: [u8; 6] = [0x60, 0xFF, 0xF0, 0x1E, 0x12, 0x02];
+
+fn running() -> Chip8 {
+    let mut chip8 = Chip8::new();
+    chip8.load(&Rom::from_bytes(LOOP).unwrap()).unwrap();
+    chip8.v[4] = 0x2A;
+    chip8.i = 0x300;
+    chip8.pc = 0x204;
+    chip8.delay_timer = 9;
+    chip8.key_down(6);
+    chip8
+}
+
+#[test]
+fn saved_state_loads_back() {
+    let state = running().save_state();
+    let mut loaded = Chip8::new();
+    loaded.load_state(&state).unwrap();
+    assert_eq!(loaded.save_state(), state);
+    assert_eq!(loaded.v[4], 0x2A);
+    assert_eq!(loaded.i, 0x300);
+    assert_eq!(loaded.pc, 0x204);
+    assert_eq!(loaded.delay_timer, 9);
+    assert!(loaded.is_key_down(6));
+}
+
+#[test]
+fn broken_states_leave_the_machine_alone() {
+    let state = running().save_state();
+    let mut chip8 = Chip8::new();
+    let before = chip8.save_state();
+
+    assert_eq!(chip8.load_state(b"C8SV"), Err(StateError::BadMagic));
+    let truncated = &state[..state.len() - 1];
+    assert_eq!(chip8.load_state(truncated), Err(StateError::Truncated));
+    let mut newer = state.clone();
+    newer[4] = 99;
+    assert_eq!(
+        chip8.load_state(&newer),
+        Err(StateError::UnsupportedVersion(99))
+    );
+    // pc follows the magic, version, ram, V0 to VF and I
+    let mut bad_pc = state.clone();
+    let pc = 5 + CHIP8_RAM + 16 + 2;
+    bad_pc[pc..pc + 2].copy_from_slice(&0xFFFFu16.to_le_bytes());
+    assert_eq!(chip8.load_state(&bad_pc), Err(StateError::Invalid("pc")));
+
+    assert_eq!(chip8.save_state(), before);
+}
+
+#[test]
+fn state_with_i_past_the_end_of_ram_loads() {
+    let mut chip8 = Chip8::new();
+    chip8.load(&Rom::from_bytes(RAISE_I).unwrap()).unwrap();
+    chip8.exec().unwrap();
+    // 17 times 0xFF is past 0xFFF
+    for _ in 0..17 {
+        chip8.pc = 0x202;
+        chip8.exec().unwrap();
+    }
+    assert_eq!(chip8.i, 17 * 0xFF);
+
+    let state = chip8.save_state();
+    let mut loaded = Chip8::new();
+    loaded.load_state(&state).unwrap();
+    assert_eq!(loaded.i, chip8.i);
+    assert_eq!(loaded.save_state(), state);
+}
+
+#[test]
+fn i_wraps_at_16_bits() {
+    let mut chip8 = Chip8::new();
+    chip8.load(&Rom::from_bytes(RAISE_I).unwrap()).unwrap();
+    chip8.exec().unwrap();
+    chip8.i = 0xFFF0;
+    chip8.exec().unwrap();
+    assert_eq!(chip8.i, 0xEF);
+}
@@ -0,0 +1,81 @@
+use chip8_rs::chip8::Chip8;
+use chip8_rs::rom::Rom;
+use chip8_rs::timing::{vip_cycles, Timing, VIP_DISPLAY_CYCLES, VIP_FRAME_CYCLES};
+
+fn load(rom: &[u8]) -> Chip8 {
+    let mut chip8 = Chip8::new();
+    chip8.load(&Rom::from_bytes(rom.to_vec()).unwrap()).unwrap();
+    chip8.timing = Timing::Vip;
+    chip8
+}
+
+#[test]
+fn unaligned_sprites_cost_more() {
+    // DRW V0, V1, 5
+    let mut chip8 = load(&[0xD0, 0x15]);
+    chip8.v[0] = 8;
+    assert_eq!(vip_cycles(&chip8), 40 + 46 + 5 * 34);
+    chip8.v[0] = 11;
+    assert_eq!(vip_cycles(&chip8), 40 + 46 + 5 * (34 + 4 * 3 + 16));
+    chip8.v[0] = 15;
+    assert!(vip_cycles(&chip8) > 40 + 46 + 5 * (34 + 4 * 3 + 16));
+}
+
+#[test]
+fn bcd_costs_a_round_per_unit_of_each_digit() {
+    // LD B, V0
+    let mut chip8 = load(&[0xF0, 0x33]);
+    let cost = |chip8: &mut Chip8, vx| {
+        chip8.v[0] = vx;
+        vip_cycles(chip8)
+    };
+    assert_eq!(cost(&mut chip8, 0), 40 + 44);
+    assert_eq!(cost(&mut chip8, 100), 40 + 44 + 16);
+    assert_eq!(cost(&mut chip8, 10), cost(&mut chip8, 100));
+    assert_eq!(cost(&mut chip8, 199), 40 + 44 + 16 * 19);
+    assert!(cost(&mut chip8, 9) > cost(&mut chip8, 200));
+}
+
+#[test]
+fn a_frame_runs_until_its_cycles_are_used_up() {
+    // ADD V0, 1; JP 0x200
+    let mut chip8 = load(&[0x70, 0x01, 0x12, 0x00]);
+    let add = vip_cycles(&chip8) as i64;
+    chip8.exec().unwrap();
+    let jump = vip_cycles(&chip8) as i64;
+    chip8.reset();
+
+    // The last instruction is the one that reaches the budget, even if it overruns it
+    let budget = VIP_FRAME_CYCLES - VIP_DISPLAY_CYCLES;
+    let pairs = budget / (add + jump);
+    let rest = budget - pairs * (add + jump);
+    chip8.frame().unwrap();
+    assert_eq!(chip8.v[0] as i64, pairs + 1);
+    assert_eq!(chip8.pc, if rest <= add { 0x202 } else { 0x200 });
+}
+
+#[test]
+fn a_frame_ends_after_a_sprite_is_drawn() {
+    // ADD V0, 1; DRW V0, V0, 1; JP 0x200
+    let mut chip8 = load(&[0x70, 0x01, 0xD0, 0x01, 0x12, 0x00]);
+    chip8.frame().unwrap();
+    assert_eq!(chip8.v[0], 1);
+    assert_eq!(chip8.pc, 0x204);
+    // The cycles Dxyn left over are not carried into the next frame
+    chip8.frame().unwrap();
+    assert_eq!(chip8.v[0], 2);
+    assert_eq!(chip8.pc, 0x204);
+}
+
+#[test]
+fn tick_rate_is_ignored_with_vip_timing() {
+    // ADD V0, 1; JP 0x200
+    let mut slow = load(&[0x70, 0x01, 0x12, 0x00]);
+    let mut fast = load(&[0x70, 0x01, 0x12, 0x00]);
+    slow.tick_rate = 1;
+    fast.tick_rate = 1000;
+    slow.frame().unwrap();
+    fast.frame().unwrap();
+    assert_eq!(slow.v[0], fast.v[0]);
+    assert!(slow.v[0] > 1);
+}
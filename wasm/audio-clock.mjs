@@ -0,0 +1,38 @@
+// AudioWorklet that keeps time by the audio hardware, which stays steady while the main
+// thread is busy and keeps going in background tabs. Send it a MessagePort as { port }
+// through the node's own port, and it posts { type: "tick", ms } to it about once per
+// 60 Hz frame, in the form worker.mjs takes:
+//
+//     await audio.audioWorklet.addModule("wasm/audio-clock.mjs");
+//     const clock = new AudioWorkletNode(audio, "chip8-clock");
+//     clock.connect(audio.destination);
+//     const channel = new MessageChannel();
+//     clock.port.postMessage({ port: channel.port1 }, [channel.port1]);
+//     worker.postMessage({ type: "clock", port: channel.port2 }, [channel.port2]);
+//
+// The node is silent, but only runs while connected and while the context is running.
+
+const TICK_MS = 1000 / 60;
+
+class ClockProcessor extends AudioWorkletProcessor {
+    constructor() {
+        super();
+        this.target = null;
+        this.pending_ms = 0;
+        this.port.onmessage = (e) => {
+            this.target = e.data.port;
+        };
+    }
+
+    process() {
+        // One render quantum is 128 frames of audio
+        this.pending_ms += (128 / sampleRate) * 1000;
+        if (this.target && this.pending_ms >= TICK_MS) {
+            this.target.postMessage({ type: "tick", ms: this.pending_ms });
+            this.pending_ms = 0;
+        }
+        return true;
+    }
+}
+
+registerProcessor("chip8-clock", ClockProcessor);
@@ -0,0 +1,79 @@
+// Runs worker.mjs under Node, for checking the worker protocol without a browser:
+//
+//     node wasm/harness.mjs <rom> [frames] [--ticks]
+//
+// Needs wasm/pkg built for the web target (wasm-pack build --target web). Loads the rom,
+// runs it until `frames` frames (default 120) came back and prints the last screen as
+// ASCII. By default the worker runs on its own timer, in real time; --ticks drives it
+// through a clock port instead, one frame's worth at a time, as fast as it keeps up.
+// Exits with 1 if the worker reported an error.
+
+import { Worker, MessageChannel } from "node:worker_threads";
+import { readFileSync } from "node:fs";
+
+const args = process.argv.slice(2);
+const ticks = args.includes("--ticks");
+const [rom_path, frames_arg] = args.filter((arg) => !arg.startsWith("--"));
+if (!rom_path) {
+    console.error("usage: node wasm/harness.mjs <rom> [frames] [--ticks]");
+    process.exit(2);
+}
+const wanted = Number(frames_arg ?? 120);
+
+const worker = new Worker(new URL("./worker.mjs", import.meta.url));
+const clock = ticks ? new MessageChannel() : null;
+let screen = null;
+let width = 64;
+let frames = 0;
+
+function tick() {
+    // A bit over one frame, so rounding never leaves a tick without one
+    clock.port1.postMessage({ type: "tick", ms: 17 });
+}
+
+function finish(code) {
+    if (screen) {
+        for (let y = 0; y < screen.length / width; y++) {
+            const row = screen.subarray(y * width, (y + 1) * width);
+            console.log(Array.from(row, (px) => (px ? "#" : ".")).join(""));
+        }
+    }
+    console.log(`${frames} frames`);
+    worker.terminate().then(() => process.exit(code));
+}
+
+worker.on("message", (message) => {
+    switch (message.type) {
+        case "loaded":
+            console.log(`loaded ${message.title ?? rom_path}`);
+            if (clock) {
+                worker.postMessage({ type: "clock", port: clock.port2 }, [clock.port2]);
+            }
+            worker.postMessage({ type: "run" });
+            if (clock) {
+                tick();
+            }
+            break;
+        case "frame":
+            screen = message.screen;
+            width = message.width;
+            frames = message.frames;
+            if (frames >= wanted) {
+                finish(0);
+            } else if (clock) {
+                tick();
+            }
+            break;
+        case "error":
+            console.error(`error: ${message.message}`);
+            finish(1);
+            break;
+    }
+});
+worker.on("error", (e) => {
+    console.error(e);
+    process.exit(1);
+});
+
+worker.postMessage({ type: "init", wasm: readFileSync(new URL("./pkg/wasm_bg.wasm", import.meta.url)) });
+worker.postMessage({ type: "load", rom: new Uint8Array(readFileSync(rom_path)) });
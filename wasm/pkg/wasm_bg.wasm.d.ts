@@ -1,17 +0,0 @@
-/* tslint:disable */
-/* eslint-disable */
-export const memory: WebAssembly.Memory;
-export function __wbg_wasmchip8_free(a: number): void;
-export function wasmchip8_new(a: number, b: number): number;
-export function wasmchip8_get_ram(a: number, b: number): void;
-export function wasmchip8_get_screen(a: number, b: number): void;
-export function wasmchip8_get_pc(a: number): number;
-export function wasmchip8_cycle(a: number, b: number, c: number): number;
-export function __wbindgen_malloc(a: number): number;
-export function __wbindgen_realloc(a: number, b: number, c: number): number;
-export const __wbindgen_export_2: WebAssembly.Table;
-export function _dyn_core__ops__function__FnMut__A____Output___R_as_wasm_bindgen__closure__WasmClosure___describe__invoke__hb8ed9e370446dd37(a: number, b: number, c: number): void;
-export function __wbindgen_add_to_stack_pointer(a: number): number;
-export function __wbindgen_free(a: number, b: number): void;
-export function __wbindgen_exn_store(a: number): void;
-export function wasm_bindgen__convert__closures__invoke2_mut__h27891ce45568b150(a: number, b: number, c: number, d: number): void;
@@ -1,44 +1,574 @@
-use chip8_rs::chip8::Chip8;
+use chip8_rs::chip8::{Chip8, Chip8Error, CHIP8_RAM, CHIP8_SCREEN_HEIGHT, CHIP8_SCREEN_WIDTH};
+use chip8_rs::emulator::{Emulator, NullAudio, NullDisplay, NullInput};
+use chip8_rs::heatmap::Heatmap;
+use chip8_rs::keymap::KeyMap;
+use chip8_rs::machine::{Framebuffer, Machine};
+use chip8_rs::profile::{Profile, StackLayout};
+use chip8_rs::rom::Rom;
+use chip8_rs::romdb::{RomDatabase, RomInfo};
+use chip8_rs::state::StateError;
+use std::time::Duration;
 use wasm_bindgen::prelude::*;
 
+// The page reads the screen and timers itself and sends keys straight to the machine,
+// so the emulator only keeps time and pauses
+type WasmEmulator = Emulator<NullDisplay, NullAudio, NullInput, Observed>;
+
 #[wasm_bindgen]
-struct WasmChip8(Chip8);
+pub struct WasmChip8 {
+    emulator: WasmEmulator,
+    rom: Rom,
+    info: Option<RomInfo>,
+    // As given to set_keymap; `keymap` adds the loaded rom's overrides
+    bindings: KeyMap,
+    keymap: KeyMap,
+}
+
+// Chip8 with the heatmap counting along while it runs
+struct Observed {
+    chip8: Chip8,
+    // Only counted while turned on with set_heatmap
+    heatmap: Option<Heatmap>,
+}
+
+impl Machine for Observed {
+    type Error = Chip8Error;
+
+    fn step(&mut self) -> Result<(), Chip8Error> {
+        if let Some(heatmap) = self.heatmap.as_mut() {
+            heatmap.observe(&self.chip8);
+        }
+        self.chip8.exec()
+    }
+
+    fn frame(&mut self) -> Result<(), Chip8Error> {
+        match self.heatmap.as_mut() {
+            Some(heatmap) => heatmap.frame(&mut self.chip8),
+            None => self.chip8.frame(),
+        }
+    }
+
+    fn tick_timers(&mut self) {
+        self.chip8.tick_timers();
+    }
+
+    fn key_down(&mut self, key: usize) {
+        self.chip8.key_down(key);
+    }
+
+    fn key_up(&mut self, key: usize) {
+        self.chip8.key_up(key);
+    }
+
+    fn framebuffer(&self) -> Framebuffer<'_> {
+        self.chip8.framebuffer()
+    }
+
+    fn sound(&self) -> bool {
+        Machine::sound(&self.chip8)
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        self.chip8.save_state()
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        self.chip8.load_state(state)
+    }
+
+    fn reset(&mut self) {
+        self.chip8.reset();
+        if let Some(heatmap) = self.heatmap.as_mut() {
+            heatmap.clear();
+        }
+    }
+
+    fn speed(&self) -> usize {
+        self.chip8.tick_rate
+    }
+
+    fn set_speed(&mut self, speed: usize) {
+        Machine::set_speed(&mut self.chip8, speed);
+    }
+}
+
+fn emulator(chip8: Chip8, heatmap: Option<Heatmap>) -> WasmEmulator {
+    Emulator::new(
+        Observed { chip8, heatmap },
+        NullDisplay,
+        NullAudio,
+        NullInput,
+    )
+}
+
+// Keys 0 to F as the page passes them, 1 for held; missing ones are up and any past F
+// are ignored
+fn keypad(input: &[usize]) -> [bool; 16] {
+    let mut keypad = [false; 16];
+    for (down, key) in keypad.iter_mut().zip(input) {
+        *down = *key == 1;
+    }
+    keypad
+}
+
+// The built in key bindings, in the format `WasmChip8::set_keymap` takes
+#[wasm_bindgen]
+pub fn default_keymap() -> String {
+    KeyMap::default().to_json().unwrap()
+}
 
 #[wasm_bindgen]
 impl WasmChip8 {
+    // Fetches the rom from `url`; needs the fetch feature
+    #[cfg(feature = "fetch")]
     pub async fn new(url: &str) -> Result<WasmChip8, JsError> {
         let response = reqwest::get(url).await?.bytes().await?;
-        let bytes: Vec<u8> = response.into();
-        let mut chip8 = Chip8::new();
-        match chip8.load_from_bin(&bytes) {
-            Err(str) => {
-                return Err(JsError::new(str));
-            }
-            _ => (),
+        Self::from_bytes(&response)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<WasmChip8, JsError> {
+        let rom = Rom::from_bytes(bytes.to_vec())?;
+        let mut chip = Self {
+            emulator: emulator(Chip8::from_known_rom(&rom)?, None),
+            info: RomDatabase::bundled().lookup(&rom).cloned(),
+            rom,
+            bindings: KeyMap::default(),
+            keymap: KeyMap::default(),
+        };
+        chip.update_keymap();
+        Ok(chip)
+    }
+
+    // Replaces the rom, e.g. with one from a file input, and starts it from scratch with
+    // the memory layout set by set_profile and friends
+    pub fn load_bytes(&mut self, bytes: &[u8]) -> Result<(), JsError> {
+        let rom = Rom::from_bytes(bytes.to_vec())?;
+        let mut chip8 = Chip8::with_profile(self.emulator.machine.chip8.profile);
+        chip8.load(&rom)?;
+        self.info = RomDatabase::bundled().lookup(&rom).cloned();
+        if let Some(info) = self.info.as_ref() {
+            chip8.apply_rom_info(info);
         }
-        Ok(Self(chip8))
+        self.restart(chip8);
+        self.rom = rom;
+        self.update_keymap();
+        Ok(())
+    }
+
+    // Key bindings as JSON (see chip8_rs::keymap::KeyMap), with the loaded rom's overrides applied
+    pub fn set_keymap(&mut self, json: &str) -> Result<(), JsError> {
+        self.bindings = KeyMap::from_json(json)?;
+        self.update_keymap();
+        Ok(())
     }
 
+    fn update_keymap(&mut self) {
+        let mut keymap = self.bindings.clone();
+        if let Some(info) = self.info.as_ref() {
+            keymap = keymap.with_rom_controls(&info.keys);
+        }
+        self.keymap = keymap.for_rom(&self.rom);
+    }
+
+    // Swaps in `chip8`, with no frame time pending and the heatmap, if on, cleared
+    fn restart(&mut self, chip8: Chip8) {
+        let mut heatmap = self.emulator.machine.heatmap.take();
+        if let Some(heatmap) = heatmap.as_mut() {
+            heatmap.clear();
+        }
+        let paused = self.emulator.paused;
+        self.emulator = emulator(chip8, heatmap);
+        self.emulator.paused = paused;
+    }
+
+    // Host key by name, e.g. "q", "1", "up", "space"
+    pub fn host_key_down(&mut self, name: &str) {
+        if let Some(key) = self.keymap.key(name) {
+            self.emulator.machine.chip8.key_down(key as usize);
+        }
+    }
+
+    pub fn host_key_up(&mut self, name: &str) {
+        if let Some(key) = self.keymap.key(name) {
+            self.emulator.machine.chip8.key_up(key as usize);
+        }
+    }
+
+    // Gamepad button by name, e.g. "a", "dpup", "start"
+    pub fn button_down(&mut self, name: &str) {
+        if let Some(key) = self.keymap.button(name) {
+            self.emulator.machine.chip8.key_down(key as usize);
+        }
+    }
+
+    pub fn button_up(&mut self, name: &str) {
+        if let Some(key) = self.keymap.button(name) {
+            self.emulator.machine.chip8.key_up(key as usize);
+        }
+    }
+
+    pub fn title(&self) -> Option<String> {
+        self.info.as_ref().map(|info| info.title.clone())
+    }
+
+    pub fn description(&self) -> Option<String> {
+        self.info.as_ref().and_then(|info| info.description.clone())
+    }
+
+    pub fn authors(&self) -> Option<String> {
+        self.info.as_ref().map(|info| info.authors.join(", "))
+    }
+
+    // Restarts the loaded rom from scratch, without fetching it again
+    pub fn reset(&mut self) {
+        self.emulator.machine.reset();
+    }
+
+    // Restarts the cpu but keeps ram and the screen
+    pub fn soft_reset(&mut self) {
+        self.emulator.machine.chip8.soft_reset();
+    }
+
+    // A copy of ram; see ram_ptr for reading it without one
     pub fn get_ram(&self) -> Vec<u8> {
-        self.0.ram.to_vec()
+        self.emulator.machine.chip8.ram().to_vec()
     }
 
+    // A copy of the screen, screen_width x screen_height; see screen_ptr for reading it
+    // without one
     pub fn get_screen(&self) -> Vec<u8> {
-        let mut ret = Vec::new();
-        self.0.screen.iter().for_each(|r| ret.extend_from_slice(r));
+        self.emulator.machine.chip8.framebuffer().pixels.to_vec()
+    }
+
+    pub fn screen_width(&self) -> usize {
+        self.emulator.machine.chip8.framebuffer().width
+    }
+
+    pub fn screen_height(&self) -> usize {
+        self.emulator.machine.chip8.framebuffer().height
+    }
+
+    // Bitplanes, each pixel holds one bit per plane
+    pub fn screen_planes(&self) -> usize {
+        self.emulator.machine.chip8.framebuffer().planes
+    }
 
-        ret
+    // Where the screen lives in wasm memory, for a view without copying:
+    //
+    //     new Uint8Array(wasm.memory.buffer, chip.screen_ptr(), chip.screen_len())
+    //
+    // with `wasm` what init() resolved to. Row by row, one byte (0 or 1) per pixel, like
+    // get_screen. The address holds for as long as this WasmChip8 lives, but a view goes
+    // stale whenever wasm memory grows, which any call into the module may cause (the
+    // old buffer is detached and the view's length drops to 0). Make views right before
+    // reading them, e.g. once per animation frame, and don't call into the module while
+    // using one.
+    pub fn screen_ptr(&self) -> *const u8 {
+        self.emulator.machine.chip8.framebuffer().pixels.as_ptr()
+    }
+
+    pub fn screen_len(&self) -> usize {
+        self.emulator.machine.chip8.framebuffer().pixels.len()
+    }
+
+    // Like screen_ptr, for the 4096 bytes of ram. For reading only: instructions
+    // written through the view wouldn't be decoded again, use set_ram instead.
+    pub fn ram_ptr(&self) -> *const u8 {
+        self.emulator.machine.chip8.ram().as_ptr()
+    }
+
+    pub fn ram_len(&self) -> usize {
+        CHIP8_RAM
     }
 
     pub fn get_pc(&self) -> usize {
-        self.0.pc
+        self.emulator.machine.chip8.pc
     }
 
-    pub async fn cycle(&mut self, input: &[usize]) {
-        let mut keypad = [false; 16];
-        for i in 0..16 {
-            keypad[i] = input[i] == 1;
+    pub fn set_pc(&mut self, pc: usize) -> Result<(), JsError> {
+        if pc > CHIP8_RAM - 2 {
+            return Err(JsError::new("pc out of range"));
         }
-        self.0.cycle(keypad);
+        self.emulator.machine.chip8.pc = pc;
+        Ok(())
+    }
+
+    // V0 to VF
+    pub fn get_v(&self) -> Vec<u8> {
+        self.emulator.machine.chip8.v.to_vec()
+    }
+
+    pub fn set_v(&mut self, x: usize, value: u8) -> Result<(), JsError> {
+        *self
+            .emulator
+            .machine
+            .chip8
+            .v
+            .get_mut(x)
+            .ok_or_else(|| JsError::new("no such register"))? = value;
+        Ok(())
+    }
+
+    pub fn get_i(&self) -> usize {
+        self.emulator.machine.chip8.i
+    }
+
+    // 16 bits, which can point past the end of ram like Fx1E can
+    pub fn set_i(&mut self, i: usize) -> Result<(), JsError> {
+        if i > 0xFFFF {
+            return Err(JsError::new("I out of range"));
+        }
+        self.emulator.machine.chip8.i = i;
+        Ok(())
+    }
+
+    pub fn get_sp(&self) -> usize {
+        self.emulator.machine.chip8.sp
+    }
+
+    pub fn set_sp(&mut self, sp: usize) -> Result<(), JsError> {
+        if sp > self.emulator.machine.chip8.stack_depth() {
+            return Err(JsError::new("stack pointer out of range"));
+        }
+        self.emulator.machine.chip8.sp = sp;
+        Ok(())
+    }
+
+    // All stack slots, including the ones above the stack pointer
+    pub fn get_stack(&self) -> Vec<u32> {
+        (0..self.emulator.machine.chip8.stack_depth())
+            .map(|slot| self.emulator.machine.chip8.stack_slot(slot) as u32)
+            .collect()
+    }
+
+    pub fn set_stack(&mut self, slot: usize, addr: usize) -> Result<(), JsError> {
+        if addr >= CHIP8_RAM {
+            return Err(JsError::new("address out of range"));
+        }
+        if slot >= self.emulator.machine.chip8.stack_depth() {
+            return Err(JsError::new("no such stack slot"));
+        }
+        self.emulator.machine.chip8.set_stack_slot(slot, addr);
+        Ok(())
+    }
+
+    // Stack depth, and where it's kept: "16" outside ram or e.g. "12@ea0" in ram at
+    // 0xEA0, like the VIP. Restarts the rom, keeping quirks and speed.
+    pub fn get_stack_layout(&self) -> String {
+        self.emulator.machine.chip8.profile.stack.to_string()
+    }
+
+    pub fn set_stack_layout(&mut self, layout: &str) -> Result<(), JsError> {
+        let stack: StackLayout = layout.parse().map_err(|e: String| JsError::new(&e))?;
+        self.relayout(self.emulator.machine.chip8.profile.with_stack(stack))
+    }
+
+    // Memory layout of the machine the rom was written for: "chip8", "vip" or "eti660",
+    // e.g. for ETI-660 roms, which load at 0x600. Restarts the rom, keeping quirks and speed.
+    pub fn set_profile(&mut self, name: &str) -> Result<(), JsError> {
+        let profile: Profile = name.parse().map_err(|e: String| JsError::new(&e))?;
+        self.relayout(profile)
+    }
+
+    // Where the rom is loaded and where it starts, for roms with their own layout.
+    // Restarts the rom, keeping quirks and speed.
+    pub fn set_load_addr(&mut self, load_addr: usize, start_addr: usize) -> Result<(), JsError> {
+        if load_addr >= CHIP8_RAM || start_addr >= CHIP8_RAM {
+            return Err(JsError::new("address out of range"));
+        }
+        let profile = self.emulator.machine.chip8.profile;
+        self.relayout(profile.with_load_addr(load_addr, start_addr))
+    }
+
+    pub fn get_load_addr(&self) -> usize {
+        self.emulator.machine.chip8.profile.load_addr
+    }
+
+    pub fn get_start_addr(&self) -> usize {
+        self.emulator.machine.chip8.profile.start_pc
+    }
+
+    fn relayout(&mut self, profile: Profile) -> Result<(), JsError> {
+        let mut chip8 = Chip8::with_profile(profile);
+        chip8.load(&self.rom)?;
+        chip8.quirks = self.emulator.machine.chip8.quirks;
+        chip8.tick_rate = self.emulator.machine.chip8.tick_rate;
+        chip8.timing = self.emulator.machine.chip8.timing;
+        self.restart(chip8);
+        Ok(())
+    }
+
+    pub fn get_delay_timer(&self) -> u8 {
+        self.emulator.machine.chip8.delay_timer
+    }
+
+    pub fn set_delay_timer(&mut self, value: u8) {
+        self.emulator.machine.chip8.delay_timer = value;
+    }
+
+    pub fn get_sound_timer(&self) -> u8 {
+        self.emulator.machine.chip8.sound_timer
+    }
+
+    pub fn set_sound_timer(&mut self, value: u8) {
+        self.emulator.machine.chip8.sound_timer = value;
+    }
+
+    // 1 for each held key, 0 to F
+    pub fn get_keypad(&self) -> Vec<u8> {
+        self.emulator
+            .machine
+            .chip8
+            .keypad
+            .iter()
+            .map(|down| *down as u8)
+            .collect()
+    }
+
+    // Instructions per 60 Hz frame
+    pub fn get_tick_rate(&self) -> usize {
+        self.emulator.machine.chip8.tick_rate
+    }
+
+    pub fn set_tick_rate(&mut self, tick_rate: usize) {
+        self.emulator.machine.chip8.tick_rate = tick_rate.max(1);
+    }
+
+    // "instructions" (tick_rate per frame) or "vip" (COSMAC VIP machine cycles per frame)
+    pub fn get_timing(&self) -> String {
+        self.emulator.machine.chip8.timing.to_string()
+    }
+
+    pub fn set_timing(&mut self, timing: &str) -> Result<(), JsError> {
+        self.emulator.machine.chip8.timing =
+            timing.parse().map_err(|e: String| JsError::new(&e))?;
+        Ok(())
+    }
+
+    pub fn set_ram(&mut self, addr: usize, value: u8) -> Result<(), JsError> {
+        if addr >= CHIP8_RAM {
+            return Err(JsError::new("address out of range"));
+        }
+        self.emulator.machine.chip8.write_ram(addr, &[value]);
+        Ok(())
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, on: bool) -> Result<(), JsError> {
+        if x >= CHIP8_SCREEN_WIDTH || y >= CHIP8_SCREEN_HEIGHT {
+            return Err(JsError::new("pixel out of range"));
+        }
+        self.emulator.machine.chip8.screen[y][x] = on as u8;
+        Ok(())
+    }
+
+    // Everything the running program can see, see chip8_rs::state
+    pub fn save_state(&self) -> Vec<u8> {
+        self.emulator.machine.chip8.save_state()
+    }
+
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), JsError> {
+        Ok(self.emulator.machine.chip8.load_state(state)?)
+    }
+
+    // A single instruction, without ticking the timers
+    pub fn step(&mut self) -> Result<(), JsError> {
+        Ok(self.emulator.machine.step()?)
+    }
+
+    // One frame with a single instruction, the keys given as for run_frame
+    pub fn cycle(&mut self, input: &[usize]) -> Result<(), JsError> {
+        let keypad = keypad(input);
+        if let Some(heatmap) = self.emulator.machine.heatmap.as_mut() {
+            heatmap.observe(&self.emulator.machine.chip8);
+        }
+        let result = self.emulator.machine.chip8.cycle(keypad);
+        if let Some(heatmap) = self.emulator.machine.heatmap.as_mut() {
+            heatmap.end_frame();
+        }
+        Ok(result?)
+    }
+
+    pub fn key_down(&mut self, key: usize) {
+        self.emulator.machine.chip8.key_down(key);
+    }
+
+    pub fn key_up(&mut self, key: usize) {
+        self.emulator.machine.chip8.key_up(key);
+    }
+
+    // One 60 Hz frame with the keys reported through key_down/key_up, or nothing while
+    // paused. Throws on an emulator error, and keeps throwing until the machine is reset.
+    pub fn frame(&mut self) -> Result<(), JsError> {
+        Ok(self.emulator.frame()?)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.emulator.paused
+    }
+
+    // Paused, frame and advance still take time but run nothing
+    pub fn set_paused(&mut self, paused: bool) {
+        self.emulator.paused = paused;
+    }
+
+    // Runs as many 60 Hz frames as fit in the time since the last call, for callers
+    // keeping their own clock (see worker.mjs), and returns how many ran. After a long
+    // stall, like a background tab, the backlog is dropped instead of fast-forwarded.
+    pub fn advance(&mut self, elapsed_ms: f64) -> Result<u32, JsError> {
+        let elapsed = Duration::from_secs_f64(elapsed_ms.max(0.0) / 1000.0);
+        Ok(self.emulator.advance(elapsed)?)
+    }
+
+    // Runs the rom's recommended number of instructions for one 60 Hz frame, with key
+    // i held if input[i] is 1. Keys past the end of `input` are up.
+    pub fn run_frame(&mut self, input: &[usize]) -> Result<(), JsError> {
+        self.emulator.machine.chip8.set_keypad(keypad(input));
+        Ok(self.emulator.frame()?)
+    }
+
+    // Starts or stops counting ram accesses for the heatmap; stopping drops the counts
+    pub fn set_heatmap(&mut self, enabled: bool) {
+        if enabled != self.emulator.machine.heatmap.is_some() {
+            self.emulator.machine.heatmap = enabled.then(Heatmap::new);
+        }
+    }
+
+    // What's left of the counts after each frame, 0.9 by default
+    pub fn set_heatmap_decay(&mut self, decay: f32) {
+        if let Some(heatmap) = self.emulator.machine.heatmap.as_mut() {
+            heatmap.decay = decay.clamp(0.0, 1.0);
+        }
+    }
+
+    // 64x64 RGBA of recent ram activity, address 0 top left: writes red, reads green,
+    // executes blue. Ready for `new ImageData(new Uint8ClampedArray(rgba), 64, 64)`.
+    // Undefined while the heatmap is off.
+    pub fn heatmap_rgba(&self) -> Option<Vec<u8>> {
+        self.emulator.machine.heatmap.as_ref().map(Heatmap::rgba)
+    }
+
+    // The decayed counts per address behind heatmap_rgba
+    pub fn heatmap_reads(&self) -> Option<Vec<f32>> {
+        self.emulator
+            .machine
+            .heatmap
+            .as_ref()
+            .map(|heatmap| heatmap.reads.clone())
+    }
+
+    pub fn heatmap_writes(&self) -> Option<Vec<f32>> {
+        self.emulator
+            .machine
+            .heatmap
+            .as_ref()
+            .map(|heatmap| heatmap.writes.clone())
+    }
+
+    pub fn heatmap_executes(&self) -> Option<Vec<f32>> {
+        self.emulator
+            .machine
+            .heatmap
+            .as_ref()
+            .map(|heatmap| heatmap.executes.clone())
     }
 }
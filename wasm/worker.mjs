@@ -0,0 +1,215 @@
+// Runs a WasmChip8 off the main thread, on its own clock, so emulation speed doesn't
+// depend on the display's refresh rate or on how busy the page is. Start it with
+// `new Worker("wasm/worker.mjs", { type: "module" })`, or from Node with worker_threads
+// (see harness.mjs), and talk to it with messages:
+//
+// To the worker
+//   { type: "init", wasm }             load the module; `wasm` (its bytes) is only needed
+//                                      where it can't be fetched, e.g. in Node
+//   { type: "load", rom }              start a rom (Uint8Array) from scratch, paused
+//   { type: "keymap", json }           key bindings, see default_keymap()
+//   { type: "key", name, down }        host key by name through the keymap, e.g. "q", "up"
+//   { type: "chip_key", key, down }    CHIP-8 key 0-F
+//   { type: "run" }, { type: "pause" }, { type: "reset" }
+//   { type: "heatmap", enabled }       count ram accesses and send them with each frame
+//   { type: "watch_ram", enabled }     send ram and the pc with each frame
+//   { type: "clock", port }            take ticks from `port` instead of the worker's own
+//                                      timer, e.g. from audio-clock.mjs; no port goes back
+//   { type: "tick", ms }               time passed, when ticks come from the main thread
+//
+// From the worker
+//   { type: "ready" }                  after init
+//   { type: "loaded", title, authors, description }
+//                                      from the rom database, or null where it has nothing
+//   { type: "frame", screen, width, height, frames, sound, heatmap, ram, pc }
+//                                      after each step of the clock that ran any frames:
+//                                      the screen (width x height bytes, 0 or 1), the total
+//                                      frame count and whether the buzzer is on; with
+//                                      heatmap on, 64x64 RGBA of ram activity (see
+//                                      heatmap_rgba); with watch_ram on, the 4096 bytes of
+//                                      ram and the pc. Buffers are transferred.
+//   { type: "error", message }         the machine is paused on a failing instruction, or
+//                                      a message couldn't be handled
+
+import init, { WasmChip8 } from "./pkg/wasm.js";
+
+const TIMER_MS = 1000 / 60;
+
+// Browsers give workers `self`, Node gives them `parentPort`; both speak MessagePort.
+const host = globalThis.self ?? (await import("node:worker_threads")).parentPort;
+
+let chip = null;
+let keymap = null;
+let running = false;
+let frames = 0;
+let timer = null;
+let last_time = 0;
+let clock_port = null;
+let heatmap = false;
+let watch_ram = false;
+
+function post(message, transfer = []) {
+    host.postMessage(message, transfer);
+}
+
+function error(e) {
+    post({ type: "error", message: e instanceof Error ? e.message : String(e) });
+}
+
+function advance(ms) {
+    if (!chip || !running) {
+        return;
+    }
+    let ran = 0;
+    let failed = null;
+    try {
+        ran = chip.advance(ms);
+    } catch (e) {
+        running = false;
+        stop_timer();
+        failed = e;
+    }
+    // After an error too, so the page shows where the machine stopped
+    if (ran > 0 || failed) {
+        frames += ran;
+        post_frame();
+    }
+    if (failed) {
+        error(failed);
+    }
+}
+
+function post_frame() {
+    const screen = chip.get_screen();
+    const message = {
+        type: "frame",
+        screen,
+        width: chip.screen_width(),
+        height: chip.screen_height(),
+        frames,
+        sound: chip.get_sound_timer() > 0,
+    };
+    const transfer = [screen.buffer];
+    if (heatmap) {
+        message.heatmap = chip.heatmap_rgba();
+        transfer.push(message.heatmap.buffer);
+    }
+    if (watch_ram) {
+        message.ram = chip.get_ram();
+        message.pc = chip.get_pc();
+        transfer.push(message.ram.buffer);
+    }
+    post(message, transfer);
+}
+
+function start_timer() {
+    stop_timer();
+    last_time = performance.now();
+    timer = setInterval(() => {
+        const now = performance.now();
+        advance(now - last_time);
+        last_time = now;
+    }, TIMER_MS);
+}
+
+function stop_timer() {
+    if (timer !== null) {
+        clearInterval(timer);
+        timer = null;
+    }
+}
+
+function use_clock(port) {
+    if (clock_port) {
+        clock_port.close();
+        clock_port = null;
+    }
+    if (port) {
+        stop_timer();
+        clock_port = port;
+        clock_port.addEventListener("message", on_message);
+        clock_port.start();
+    } else if (running) {
+        start_timer();
+    }
+}
+
+async function handle(message) {
+    switch (message.type) {
+        case "init":
+            await init(message.wasm);
+            post({ type: "ready" });
+            break;
+        case "load":
+            chip = WasmChip8.from_bytes(message.rom);
+            if (keymap !== null) {
+                chip.set_keymap(keymap);
+            }
+            chip.set_heatmap(heatmap);
+            frames = 0;
+            running = false;
+            stop_timer();
+            post({
+                type: "loaded",
+                title: chip.title() ?? null,
+                authors: chip.authors() ?? null,
+                description: chip.description() ?? null,
+            });
+            break;
+        case "keymap":
+            keymap = message.json;
+            chip?.set_keymap(keymap);
+            break;
+        case "key":
+            if (message.down) {
+                chip?.host_key_down(message.name);
+            } else {
+                chip?.host_key_up(message.name);
+            }
+            break;
+        case "chip_key":
+            if (message.down) {
+                chip?.key_down(message.key);
+            } else {
+                chip?.key_up(message.key);
+            }
+            break;
+        case "run":
+            running = chip !== null;
+            if (running && clock_port === null) {
+                start_timer();
+            }
+            break;
+        case "pause":
+            running = false;
+            stop_timer();
+            break;
+        case "reset":
+            chip?.reset();
+            frames = 0;
+            break;
+        case "heatmap":
+            heatmap = message.enabled;
+            chip?.set_heatmap(heatmap);
+            break;
+        case "watch_ram":
+            watch_ram = message.enabled;
+            break;
+        case "clock":
+            use_clock(message.port);
+            break;
+        case "tick":
+            advance(message.ms);
+            break;
+        default:
+            throw new Error(`unknown message type ${message.type}`);
+    }
+}
+
+// One message at a time, so e.g. a load sent right after init waits for the module
+let queue = Promise.resolve();
+function on_message(e) {
+    queue = queue.then(() => handle(e.data)).catch(error);
+}
+
+host.addEventListener("message", on_message);